@@ -2,15 +2,26 @@
 use bevy::prelude::*;
 // use bevy::time::common_conditions::on_timer;
 use avian3d::prelude::*;
+use std::f32::consts::TAU;
 
-use crate::missile::Missile;
 use crate::Health;
+use crate::layers::asteroid_layers;
+use crate::effect_pool::{EffectKind, PlayEffect};
+use crate::damage::{DamageEvent, DamageKind, DamageSystems, Died};
+use crate::collision_routing::AsteroidsCollided;
+use crate::field::{generate, AsteroidSpec, FieldConfig};
+use crate::rocks::{rock, ROCK};
+use crate::gravity::Orbit;
+use crate::waves::WaveMember;
+use crate::mining::{Composition, Yield, COMPOSITIONS, ORE_YIELD};
 pub struct AsteroidsPlugin;
 impl Plugin for AsteroidsPlugin{
     fn build(&self, app: &mut App) {
         app
+        .init_resource::<FieldConfig>()
         .add_systems(Startup, spawn)
-        .add_systems(Update, (collision, check).chain().run_if(on_event::<CollisionEnded>()))
+        .add_systems(Update, destroy.after(DamageSystems).run_if(on_event::<Died>()))
+        .add_systems(Update, impact.run_if(on_event::<AsteroidsCollided>()))
         ;
     }
 }
@@ -23,108 +34,164 @@ pub struct Asteroid;
 #[derive(Component)]
 pub struct AsteroidType(usize);
 
+// Index of the rock shape, fragments keep it: hulls differ in volume, and so in mass at equal scale
+
+#[derive(Component)]
+pub struct AsteroidShape(usize);
+
+// Large ones split into medium, medium into small, small ones are gone for good
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AsteroidSize {
+    Large,
+    Medium,
+    Small
+}
+
+impl AsteroidSize {
+    fn next(&self) -> Option<Self> {
+        match self {
+            Self::Large => Some(Self::Medium),
+            Self::Medium => Some(Self::Small),
+            Self::Small => None
+        }
+    }
+
+    pub fn health_factor(&self) -> f32 {
+        match self {
+            Self::Large => 1.,
+            Self::Medium => 0.5,
+            Self::Small => 0.25
+        }
+    }
+}
 
 const ASTEROID_HEALTH: f32 = 10.;
+const ASTEROID_IMPACT_THRESHOLD: f32 = 20000.;
+const ASTEROID_IMPACT_DAMAGE: f32 = 0.0002;    // per unit of impulse above the threshold
 
-#[derive(Resource)] 
-pub struct MatMeshes(Vec<(Handle<Mesh>, Handle<StandardMaterial>)>);
+const FRAGMENT_COUNT: (u32, u32) = (2, 4);
+const FRAGMENT_SPREAD_SPEED: f32 = 6.;
+const FRAGMENT_MIN_SCALE: f32 = 0.2;           // smaller pieces turn into dust
 
-use crate::LifeTime;
+// Materials per asteroid type, procedural rocks (mesh and the matching hull) shared by all types
+
+#[derive(Resource)] 
+pub struct MatMeshes {
+    materials: Vec<Handle<StandardMaterial>>,
+    shapes: Vec<(Handle<Mesh>, Collider)>
+}
 
 // ==================================
 
 fn spawn(
     mut commands: Commands,
     assets: ResMut<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    config: Res<FieldConfig>,
 ) {
-    let mut mm:Vec<(Handle<Mesh>, Handle<StandardMaterial>)> = Vec::new();
-    let range_xz = -800 .. 800;
-    let range_y = 100 .. 200;
-    let deviation_range = -100..100;
-     
-    for i in 0..3 {
-        let mesh = assets.load(format!("models/asteroids.glb#Mesh{}/Primitive0", i));
-        let material = assets.load(format!("models/asteroids.glb#Material{}", i));
-        mm.push((
-            mesh.clone(), material.clone() 
-        ));
-
-        for _j in 0..20 {
-            let initial_pos = Vec3::new(fastrand::i32(range_xz.clone()) as _ , fastrand::i32(range_y.clone()) as _, fastrand::i32(range_xz.clone()) as _);
-            let target = Vec3::splat(0.) + Vec3::new(fastrand::i32(deviation_range.clone()) as _ , fastrand::i32(deviation_range.clone()) as _, fastrand::i32(deviation_range.clone()) as _);
-            commands.spawn((
-                PbrBundle {
-                    mesh : mesh.clone(),
-                    material : material.clone(),
-                    transform: Transform::from_translation(initial_pos),
-                    ..default()
-                },
-                Asteroid,
-                AsteroidType(i),
-                Health(ASTEROID_HEALTH),
-                RigidBody::Dynamic,
-                GravityScale(0.),
-                Collider::sphere(10.),
-                ExternalImpulse::new((target - initial_pos) * 50.),
-                ExternalAngularImpulse::new(Vec3::Y * 2.)
-            ));    
-        }
+    let materials = (0..3).map(|i| assets.load(format!("models/asteroids.glb#Material{}", i))).collect();
+    let shapes = (0 .. config.shapes).map(|i| {
+        let (mesh, collider) = rock(config.seed.wrapping_add(i as u64), &ROCK);
+        (meshes.add(mesh), collider)
+    }).collect();
+    let mm = MatMeshes {materials, shapes};
+
+    info!("asteroid field seed: {}", config.seed);
+    for spec in generate(&config) {
+        spawn_asteroid(&mut commands, &mm, &spec);
     }
 
-    commands.insert_resource(MatMeshes(mm));
+    commands.insert_resource(mm);
 
 } 
 
 // ---
 
-fn collision(
-    mut collision_events: EventReader<CollisionEnded>,
-    mut e_q: Query<(Entity, &mut Health), With<Asteroid>>,
-    m_q: Query<Entity, With<Missile>>
-) {
-    for CollisionEnded(e1, e2)  in  collision_events.read() {
-        for (_, mut h) in e_q.iter_mut().filter(|(e, _)| {e == e1 || e == e2}) {
-            if m_q.contains(*e1) || m_q.contains(*e2) {
-                h.0 -= 10.;
-            } 
-            break;    
-        }
+pub fn spawn_asteroid(commands: &mut Commands, mm: &MatMeshes, spec: &AsteroidSpec) -> Entity {
+    let (mesh, collider) = &mm.shapes[spec.shape];
+    let mut asteroid = commands.spawn((
+        PbrBundle {
+            mesh : mesh.clone(),
+            material : mm.materials[spec.kind].clone(),
+            transform: Transform::from_translation(spec.position).with_scale(Vec3::splat(spec.scale)),
+            ..default()
+        },
+        Asteroid,
+        AsteroidType(spec.kind),
+        AsteroidShape(spec.shape),
+        COMPOSITIONS[spec.kind],
+        spec.size,
+        Health::new(ASTEROID_HEALTH * spec.toughness * spec.size.health_factor()),
+        RigidBody::Dynamic,
+        GravityScale(0.),
+        collider.clone(),
+        asteroid_layers(),
+        LinearVelocity(spec.velocity),
+        AngularVelocity(spec.spin)
+    ));
+    if let Some(orbit) = spec.orbit {
+        asteroid.insert(orbit);
     }
+    asteroid.id()
 }
 
 // ---
 
-fn check(
+// Colliders share one density, so mass goes with scale cubed:
+// n fragments of scale * cbrt(1/n) weigh as much as the parent.
+// Spread directions are spaced evenly over a sphere, their mean is taken out of the spread velocity
+// so the fragments keep exactly the parent's momentum
+
+fn destroy(
     mut commands: Commands,
-    e_q: Query<(Entity, &Health, &AsteroidType, &Transform), With<Asteroid>>,
+    mut reader: EventReader<Died>,
+    e_q: Query<(&AsteroidType, &AsteroidShape, &Composition, &AsteroidSize, &Health, &Transform, &LinearVelocity, &AngularVelocity, Option<&Orbit>, Has<WaveMember>), With<Asteroid>>,
     mm: Res<MatMeshes>,
-    time: Res<Time>
+    mut fx_writer: EventWriter<PlayEffect>,
+    mut yield_writer: EventWriter<Yield>,
 ) {
     
-    for (e, h, at, tr) in e_q.iter() {
-        if h.0 <= 0. {
-            commands.entity(e).despawn_recursive();
-            let fragment_count =  fastrand::u32(10..20) as usize;  
-            for i in fibonacci_sphere(fragment_count) {
-                let scale = fastrand::u32(1..6) as f32 / 10.;
-                commands.spawn((
-                    PbrBundle {
-                        mesh : mm.0[at.0].0.clone(),
-                        material: mm.0[at.0].1.clone(),
-                        transform: Transform::from_translation(tr.translation + i).with_scale(Vec3::splat(scale) * tr.scale.x),
-                        ..default()
-                    },
-                    Collider::sphere(10. * scale * tr.scale.x),
-                    LinearDamping(0.5),
-                    AngularDamping(0.8),
-                    Asteroid,
-                    AsteroidType(at.0),
-                    GravityScale(0.),
-                    RigidBody::Dynamic,
-                    LifeTime(time.elapsed_seconds() + 5.),
-                    ExternalImpulse::new(i),
-                    ExternalAngularImpulse::new(i)
-                ));
+    for ev in reader.read() {
+        let Ok((at, shape, composition, size, health, tr, lv, av, orbit, wave_member)) = e_q.get(ev.entity) else {
+            continue;
+        };
+        commands.entity(ev.entity).despawn_recursive();
+        fx_writer.send(PlayEffect(EffectKind::Debris, tr.translation));
+
+        let count = fastrand::u32(FRAGMENT_COUNT.0 ..= FRAGMENT_COUNT.1) as usize;
+        let scale = tr.scale.x * (1. / count as f32).cbrt();
+        let Some(next) = size.next().filter(|_| scale >= FRAGMENT_MIN_SCALE) else {
+            // nothing left to split, the whole piece is mined
+            let amount = ORE_YIELD * tr.scale.x.powi(3);
+            yield_writer.send(Yield {
+                amounts: composition.0.map(|share| share * amount),
+                point: tr.translation,
+                velocity: lv.0
+            });
+            continue;
+        };
+        let toughness = health.max() / (ASTEROID_HEALTH * size.health_factor());
+        let turn = Quat::from_euler(EulerRot::XYZ, fastrand::f32() * TAU, fastrand::f32() * TAU, fastrand::f32() * TAU);
+
+        let dirs: Vec<Vec3> = fibonacci_sphere(count).into_iter().map(|d| turn * d).collect();
+        let mean = dirs.iter().sum::<Vec3>() / count as f32;
+
+        for dir in dirs {
+            let fragment = spawn_asteroid(&mut commands, &mm, &AsteroidSpec {
+                kind: at.0,
+                shape: shape.0,
+                position: tr.translation + dir * ROCK.radius * scale * 1.1,
+                scale,
+                velocity: lv.0 + (dir - mean) * FRAGMENT_SPREAD_SPEED,
+                spin: av.0 + dir.cross(Vec3::Y) * 0.5,
+                toughness,
+                size: next,
+                orbit: orbit.copied()
+            });
+            // the wave isn't over while its pieces are still flying
+            if wave_member {
+                commands.entity(fragment).insert(WaveMember);
             }
         }
     }
@@ -132,6 +199,31 @@ fn check(
 
 // ---
 
+// Only hard hits chip asteroids, grazing contacts inside the field do nothing
+
+fn impact(
+    mut hit_events: EventReader<AsteroidsCollided>,
+    mut damage_writer: EventWriter<DamageEvent>,
+) {
+    for hit in hit_events.read() {
+        if hit.impulse <= ASTEROID_IMPACT_THRESHOLD {
+            continue;
+        }
+        let amount = (hit.impulse - ASTEROID_IMPACT_THRESHOLD) * ASTEROID_IMPACT_DAMAGE;
+        for (target, source) in [(hit.a, hit.b), (hit.b, hit.a)] {
+            damage_writer.send(DamageEvent {
+                source,
+                target,
+                amount,
+                kind: DamageKind::Impact,
+                point: hit.point
+            });
+        }
+    }
+}
+
+// ---
+
 fn fibonacci_sphere(count: usize) -> Vec<Vec3> {
     let phi = std::f32::consts::PI * (5.0_f32.sqrt() - 1.);
     (0 .. count).map(|i| {
@@ -0,0 +1,274 @@
+use bevy::prelude::*;
+use avian3d::prelude::*;
+
+use crate::{GameOver, GameState, Health, NotReady, Target};
+use crate::layers::dock_layers;
+use crate::collision_routing::BaseHit;
+use crate::damage::{DamageEvent, DamageKind, DamageSystems, Died};
+use crate::effect_pool::{EffectKind, PlayEffect};
+use crate::ui::{RegisterWidgets, ULayout, UpdateWidgets, WType, WidgetRegData, WidgetUpdateData};
+use bevy::color::palettes::css::{ORANGE_RED, YELLOW_GREEN};
+
+pub struct BasePlugin;
+impl Plugin for BasePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn);
+        app.add_systems(Update, setup.run_if(in_state(GameState::Setup)));
+        app.add_systems(Update, impact.run_if(on_event::<BaseHit>()));
+        app.add_systems(Update, lost.after(DamageSystems).run_if(on_event::<Died>()));
+        app.add_systems(Update, update_indicators.run_if(in_state(GameState::Game)));
+        app.add_systems(OnEnter(GameState::Over), over);
+    }
+}
+
+// ---
+
+// The station the asteroids are heading for. One static body, each module is a hitbox of its own,
+// while the hull integrity (Health) is shared by the whole base
+
+#[derive(Component)]
+pub struct Base;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ModuleKind {
+    Core,
+    Habitat,
+    Solar,
+    Pad,
+    DockArm,
+    Turret,
+    Shield,
+    Refinery
+}
+
+impl ModuleKind {
+    // hits on vital parts hurt more
+
+    fn damage_factor(&self) -> f32 {
+        match self {
+            Self::Core | Self::Shield => 2.,
+            Self::Habitat | Self::Refinery => 1.,
+            Self::Solar | Self::Pad | Self::DockArm | Self::Turret => 0.5
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct BaseModule(pub ModuleKind);
+
+// Where built modules snap on, facing away from the core
+
+#[derive(Component)]
+pub struct AttachPoint {
+    pub occupied: bool
+}
+
+const CORE_CENTER: Vec3 = Vec3::new(0., 17., 0.);
+
+const ATTACH_POINTS: [Vec3; 6] = [
+    Vec3::new(0., 36., 0.),
+    Vec3::new(0., 17., 32.),
+    Vec3::new(0., 17., -32.),
+    Vec3::new(52., 17., 0.),
+    Vec3::new(-52., 17., 0.),
+    Vec3::new(0., -6., 0.),
+];
+
+#[derive(Component)]
+pub struct BaseTempMarker;
+
+const BASE_HEALTH: f32 = 1000.;
+const BASE_IMPACT_MIN: f32 = 2.;
+const BASE_IMPACT_DAMAGE: f32 = 0.0005;    // per unit of impulse
+const BASE_CRUSH_DAMAGE: f32 = 10.;        // to the asteroid that hit the base
+const SHIELD_ABSORB: f32 = 0.6;            // damage let through per shield generator
+
+const I_BASE: (&str, &str) = ("base", "Base");
+const I_GOAL: (&str, &str) = ("goal", "Goal");
+
+// ---
+
+fn spawn(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    commands.spawn((NotReady, BaseTempMarker));
+
+    let hull = materials.add(Color::srgb(0.5, 0.52, 0.55));
+    let panel = materials.add(StandardMaterial {
+        base_color: Color::srgb(0.1, 0.15, 0.4),
+        reflectance: 1.,
+        ..default()
+    });
+    let pad = materials.add(Color::srgb(0.05, 0.05, 0.05));
+
+    let modules = [
+        (ModuleKind::Pad, meshes.add(Cuboid::new(60., 2., 60.)), Collider::cuboid(60., 2., 60.), pad, Vec3::ZERO),
+        (ModuleKind::Core, meshes.add(Sphere::new(15.)), Collider::sphere(15.), hull.clone(), CORE_CENTER),
+        (ModuleKind::Habitat, meshes.add(Cuboid::new(8., 8., 50.)), Collider::cuboid(8., 8., 50.), hull.clone(), Vec3::new(0., 17., 0.)),
+        (ModuleKind::Solar, meshes.add(Cuboid::new(30., 1., 14.)), Collider::cuboid(30., 1., 14.), panel.clone(), Vec3::new(-32., 17., 0.)),
+        (ModuleKind::Solar, meshes.add(Cuboid::new(30., 1., 14.)), Collider::cuboid(30., 1., 14.), panel, Vec3::new(32., 17., 0.)),
+    ];
+
+    commands.spawn((
+        SpatialBundle::default(),
+        Name::new("Base"),
+        Base,
+        Health::new(BASE_HEALTH),
+        RigidBody::Static,
+        Target
+    ))
+    .with_children(|p| {
+        for (kind, mesh, collider, material, position) in modules {
+            p.spawn((
+                PbrBundle {
+                    mesh,
+                    material,
+                    transform: Transform::from_translation(position),
+                    ..default()
+                },
+                BaseModule(kind),
+                collider,
+                dock_layers(),
+            ));
+        }
+        for position in ATTACH_POINTS {
+            let out = (position - CORE_CENTER).normalize();
+            let up = if out.y.abs() > 0.9 {Vec3::Z} else {Vec3::Y};
+            p.spawn((
+                SpatialBundle::from_transform(Transform::from_translation(position).looking_to(out, up)),
+                AttachPoint {occupied: false}
+            ));
+        }
+    });
+}
+
+// ---
+
+fn setup (
+    mut commands: Commands,
+    check_q: Query<Entity, (With<NotReady>, With<BaseTempMarker>)>,
+    mut writer: EventWriter<RegisterWidgets>,
+) {
+    let Ok(e) = check_q.get_single() else {
+        return;
+    };
+    writer.send(
+        RegisterWidgets(
+            vec![
+                WidgetRegData {
+                    key: I_BASE.0,
+                    label: I_BASE.1,
+                    parent: ULayout::Footer,
+                    wtype: WType::Float,
+                    image: None,
+                    start: 10,
+                    span: 1,
+                    default: Some(100.)
+                },
+                WidgetRegData {
+                    key: I_GOAL.0,
+                    label: I_GOAL.1,
+                    parent: ULayout::Content,
+                    wtype: WType::Text,
+                    image: None,
+                    start: 1,
+                    span: 1,
+                    default: None
+                },
+            ]
+        )
+    );
+    commands.entity(e).despawn();
+}
+
+// ---
+
+fn impact(
+    mut hit_events: EventReader<BaseHit>,
+    module_q: Query<(&BaseModule, &Parent)>,
+    mut damage_writer: EventWriter<DamageEvent>,
+) {
+    let shields = module_q.iter().filter(|(m, _)| m.0 == ModuleKind::Shield).count();
+    let absorb = SHIELD_ABSORB.powi(shields as i32);
+    for hit in hit_events.read() {
+        let Ok((module, parent)) = module_q.get(hit.module) else {
+            continue;
+        };
+        damage_writer.send(DamageEvent {
+            source: hit.asteroid,
+            target: parent.get(),
+            amount: (BASE_IMPACT_MIN + hit.impulse * BASE_IMPACT_DAMAGE) * module.0.damage_factor() * absorb,
+            kind: DamageKind::Impact,
+            point: hit.point
+        });
+        damage_writer.send(DamageEvent {
+            source: parent.get(),
+            target: hit.asteroid,
+            amount: BASE_CRUSH_DAMAGE,
+            kind: DamageKind::Impact,
+            point: hit.point
+        });
+    }
+}
+
+// ---
+
+fn lost(
+    mut commands: Commands,
+    mut reader: EventReader<Died>,
+    base_q: Query<&Children, With<Base>>,
+    module_q: Query<&GlobalTransform, With<BaseModule>>,
+    mut fx_writer: EventWriter<PlayEffect>,
+    mut writer: EventWriter<UpdateWidgets>,
+    mut next: ResMut<NextState<GameState>>,
+    mut physics_time: ResMut<Time<Physics>>,
+) {
+    for ev in reader.read() {
+        let Ok(children) = base_q.get(ev.entity) else {
+            continue;
+        };
+        for child in children.iter() {
+            if let Ok(t) = module_q.get(*child) {
+                fx_writer.send(PlayEffect(EffectKind::Blast, t.translation()));
+            }
+        }
+        writer.send(UpdateWidgets(vec![
+            WidgetUpdateData::from_key_value_color(I_BASE.0, 0., ORANGE_RED.into())
+        ]));
+        commands.insert_resource(GameOver("Base lost. Game over"));
+        physics_time.pause();
+        next.set(GameState::Over);
+    }
+}
+
+// ---
+
+fn update_indicators(
+    base_q: Query<&Health, (With<Base>, Changed<Health>)>,
+    mut writer: EventWriter<UpdateWidgets>
+) {
+    if let Ok(health) = base_q.get_single() {
+        let color = if health.percent() < 0.3 {ORANGE_RED.into()} else {YELLOW_GREEN.into()};
+        writer.send(UpdateWidgets(vec![
+            WidgetUpdateData::from_key_value_color(I_BASE.0, health.percent() * 100., color),
+            WidgetUpdateData::from_key_text(I_GOAL.0, "Defend the base")
+        ]));
+    }
+}
+
+// ---
+
+// Runs after the last frame of the game, nothing overwrites the goal afterwards
+
+fn over(
+    reason: Option<Res<GameOver>>,
+    mut writer: EventWriter<UpdateWidgets>
+) {
+    if let Some(reason) = reason {
+        writer.send(UpdateWidgets(vec![
+            WidgetUpdateData::from_key_text(I_GOAL.0, reason.0)
+        ]));
+    }
+}
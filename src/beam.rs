@@ -0,0 +1,104 @@
+use bevy::prelude::*;
+use bevy::pbr::{NotShadowCaster, NotShadowReceiver};
+
+pub struct BeamPlugin;
+impl Plugin for BeamPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, startup);
+        app.add_systems(Update, fade);
+    }
+}
+
+// ---
+
+// Stretched emissive cylinder, lit to full intensity by `fire` and fading out on its own
+
+#[derive(Component)]
+pub struct Beam {
+    color: LinearRgba,
+    intensity: f32,
+    material: Handle<StandardMaterial>
+}
+
+#[derive(Resource)]
+pub struct BeamAssets {
+    mesh: Handle<Mesh>
+}
+
+const BEAM_FADE_RATE: f32 = 8.;   // full intensity to zero in 1/8 s
+const BEAM_GLOW: f32 = 20.;
+
+// ---
+
+fn startup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    commands.insert_resource(BeamAssets {
+        mesh: meshes.add(Cylinder::new(1., 1.))
+    });
+}
+
+// ---
+
+pub fn spawn_beam(
+    commands: &mut Commands,
+    assets: &BeamAssets,
+    materials: &mut Assets<StandardMaterial>,
+    color: LinearRgba
+) -> Entity {
+    let material = materials.add(StandardMaterial {
+        base_color: Color::NONE,
+        emissive: LinearRgba::BLACK,
+        alpha_mode: AlphaMode::Blend,
+        ..default()
+    });
+    commands.spawn((
+        PbrBundle {
+            mesh: assets.mesh.clone(),
+            material: material.clone(),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        Beam {color, intensity: 0., material},
+        NotShadowCaster,
+        NotShadowReceiver
+    )).id()
+}
+
+// ---
+
+pub fn fire(beam: &mut Beam, transform: &mut Transform, visibility: &mut Visibility, from: Vec3, to: Vec3, width: f32) {
+    let span = to - from;
+    let length = span.length();
+    if length < f32::EPSILON {
+        return;
+    }
+    transform.translation = from + span * 0.5;
+    transform.rotation = Quat::from_rotation_arc(Vec3::Y, span / length);
+    transform.scale = Vec3::new(width, length, width);
+    beam.intensity = 1.;
+    *visibility = Visibility::Inherited;
+}
+
+// ---
+
+fn fade(
+    mut beam_q: Query<(&mut Beam, &mut Visibility)>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    time: Res<Time>
+) {
+    for (mut beam, mut visibility) in beam_q.iter_mut() {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        if let Some(material) = materials.get_mut(&beam.material) {
+            material.base_color = Color::from(beam.color).with_alpha(beam.intensity);
+            material.emissive = beam.color * BEAM_GLOW * beam.intensity;
+        }
+        if beam.intensity <= 0. {
+            *visibility = Visibility::Hidden;
+        }
+        beam.intensity = f32::max(beam.intensity - BEAM_FADE_RATE * time.delta_seconds(), 0.);
+    }
+}
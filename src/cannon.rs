@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+use avian3d::prelude::*;
+
+use crate::LifeTime;
+use crate::layers::projectile_layers;
+use crate::damage::{DamageEvent, DamageKind};
+use crate::collision_routing::SlugHit;
+use crate::effect_pool::{EffectKind, PlayEffect};
+use crate::weapon::{Projectile, WeaponShot};
+
+pub struct CannonPlugin;
+impl Plugin for CannonPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, startup);
+        app.add_systems(Update, shot.run_if(on_event::<WeaponShot>()));
+        app.add_systems(Update, collision.run_if(on_event::<SlugHit>()));
+    }
+}
+
+// ---
+
+#[derive(Component)]
+pub struct Slug {
+    owner: Entity,
+    damage: f32
+}
+
+#[derive(Resource)]
+pub struct SlugAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>
+}
+
+const SLUG_RADIUS: f32 = 0.15;
+const SLUG_SPEED: f32 = 150.;
+
+// ---
+
+fn startup(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    commands.insert_resource(SlugAssets {
+        mesh: meshes.add(Sphere::new(SLUG_RADIUS)),
+        material: materials.add(StandardMaterial {
+            base_color: Color::srgb(1., 0.8, 0.4),
+            emissive: LinearRgba::rgb(4., 2., 0.5),
+            ..default()
+        })
+    });
+}
+
+// ---
+
+fn shot(
+    mut commands: Commands,
+    mut ev_reader: EventReader<WeaponShot>,
+    shooter_q: Query<(&Transform, Option<&LinearVelocity>)>,
+    assets: Res<SlugAssets>,
+    time: Res<Time>,
+) {
+    for WeaponShot(se, weapon, _) in ev_reader.read() {
+        if weapon.projectile != Projectile::Slug {
+            continue;
+        }
+        let Ok((shooter_trans, olv)) = shooter_q.get(*se) else {
+            continue;
+        };
+        let inherited = if let Some(lv) = olv {lv.0} else {Vec3::ZERO};
+        commands.spawn((
+            PbrBundle {
+                material: assets.material.clone(),
+                mesh: assets.mesh.clone(),
+                transform: Transform::from_translation(shooter_trans.translation + shooter_trans.forward() * 10.),
+                ..default()
+            },
+            Slug {owner: *se, damage: weapon.damage},
+            LifeTime(time.elapsed_seconds() + weapon.range / SLUG_SPEED),
+            RigidBody::Dynamic,
+            GravityScale(0.),
+            Collider::sphere(SLUG_RADIUS),
+            projectile_layers(),
+            LinearVelocity(inherited + shooter_trans.forward() * SLUG_SPEED),
+        ));
+    }
+}
+
+// ---
+
+fn collision(
+    mut commands: Commands,
+    mut hit_events: EventReader<SlugHit>,
+    slug_q: Query<&Slug>,
+    mut fx_writer: EventWriter<PlayEffect>,
+    mut damage_writer: EventWriter<DamageEvent>,
+) {
+    for hit in hit_events.read() {
+        let Ok(slug) = slug_q.get(hit.slug) else {
+            continue;
+        };
+        damage_writer.send(DamageEvent {
+            source: slug.owner,
+            target: hit.other,
+            amount: slug.damage,
+            kind: DamageKind::Kinetic,
+            point: hit.point
+        });
+        fx_writer.send(PlayEffect(EffectKind::Impact, hit.point));
+        commands.entity(hit.slug).despawn_recursive();
+    }
+}
@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+use avian3d::prelude::*;
+
+use crate::asteroids::Asteroid;
+use crate::base::BaseModule;
+use crate::cannon::Slug;
+use crate::docks::Dock;
+use crate::drone::Drone;
+use crate::missile::Missile;
+use crate::pickups::Pickup;
+
+pub struct CollisionRoutingPlugin;
+impl Plugin for CollisionRoutingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, route.run_if(on_event::<CollisionStarted>()));
+        app.add_event::<MissileHit>();
+        app.add_event::<SlugHit>();
+        app.add_event::<DroneAsteroidHit>();
+        app.add_event::<AsteroidsCollided>();
+        app.add_event::<DroneDockContact>();
+        app.add_event::<PickupContact>();
+        app.add_event::<BaseHit>();
+    }
+}
+
+// ---
+
+// Projectiles report a hit with whatever they touched
+
+#[derive(Event)]
+pub struct MissileHit {
+    pub missile: Entity,
+    pub other: Entity,
+    pub point: Vec3,
+}
+
+#[derive(Event)]
+pub struct SlugHit {
+    pub slug: Entity,
+    pub other: Entity,
+    pub point: Vec3,
+}
+
+#[derive(Event)]
+pub struct DroneAsteroidHit {
+    pub drone: Entity,
+    pub asteroid: Entity,
+    pub point: Vec3,
+    pub impulse: f32
+}
+
+#[derive(Event)]
+pub struct AsteroidsCollided {
+    pub a: Entity,
+    pub b: Entity,
+    pub point: Vec3,
+    pub impulse: f32
+}
+
+#[derive(Event)]
+pub struct DroneDockContact {
+    pub drone: Entity,
+    pub dock: Entity,
+}
+
+#[derive(Event)]
+pub struct PickupContact {
+    pub drone: Entity,
+    pub pickup: Entity,
+}
+
+#[derive(Event)]
+pub struct BaseHit {
+    pub module: Entity,
+    pub asteroid: Entity,
+    pub point: Vec3,
+    pub impulse: f32
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Role {
+    Missile,
+    Slug,
+    Asteroid,
+    Drone,
+    Dock,
+    Pickup,
+    Base,
+    Other
+}
+
+// ---
+
+fn route(
+    mut collision_events: EventReader<CollisionStarted>,
+    collisions: Res<Collisions>,
+    role_q: Query<(Has<Missile>, Has<Slug>, Has<Asteroid>, Has<Drone>, Has<Dock>, Has<Pickup>, Has<BaseModule>)>,
+    transform_q: Query<&Transform>,
+    mut missile_writer: EventWriter<MissileHit>,
+    mut slug_writer: EventWriter<SlugHit>,
+    mut drone_asteroid_writer: EventWriter<DroneAsteroidHit>,
+    mut asteroids_writer: EventWriter<AsteroidsCollided>,
+    mut dock_writer: EventWriter<DroneDockContact>,
+    mut pickup_writer: EventWriter<PickupContact>,
+    mut base_writer: EventWriter<BaseHit>,
+) {
+    let role = |e: Entity| -> Role {
+        match role_q.get(e) {
+            Ok((true, _, _, _, _, _, _)) => Role::Missile,
+            Ok((_, true, _, _, _, _, _)) => Role::Slug,
+            Ok((_, _, true, _, _, _, _)) => Role::Asteroid,
+            Ok((_, _, _, true, _, _, _)) => Role::Drone,
+            Ok((_, _, _, _, true, _, _)) => Role::Dock,
+            Ok((_, _, _, _, _, true, _)) => Role::Pickup,
+            Ok((_, _, _, _, _, _, true)) => Role::Base,
+            _ => Role::Other
+        }
+    };
+
+    for CollisionStarted(e1, e2) in collision_events.read() {
+        let (point, impulse) = contact(&collisions, &transform_q, *e1, *e2);
+        for (a, b) in [(*e1, *e2), (*e2, *e1)] {
+            match (role(a), role(b)) {
+                (Role::Missile, _) => {
+                    missile_writer.send(MissileHit {missile: a, other: b, point});
+                },
+                (Role::Slug, _) => {
+                    slug_writer.send(SlugHit {slug: a, other: b, point});
+                },
+                (Role::Drone, Role::Asteroid) => {
+                    drone_asteroid_writer.send(DroneAsteroidHit {drone: a, asteroid: b, point, impulse});
+                },
+                (Role::Drone, Role::Dock) => {
+                    dock_writer.send(DroneDockContact {drone: a, dock: b});
+                },
+                (Role::Drone, Role::Pickup) => {
+                    pickup_writer.send(PickupContact {drone: a, pickup: b});
+                },
+                (Role::Base, Role::Asteroid) => {
+                    base_writer.send(BaseHit {module: a, asteroid: b, point, impulse});
+                },
+                (Role::Asteroid, Role::Asteroid) if a < b => {
+                    asteroids_writer.send(AsteroidsCollided {a, b, point, impulse});
+                },
+                _ => ()
+            }
+        }
+    }
+}
+
+// ---
+
+// World space contact point and total normal impulse of the pair,
+// falls back to the midpoint when the contact is already gone
+
+fn contact(collisions: &Collisions, transform_q: &Query<&Transform>, e1: Entity, e2: Entity) -> (Vec3, f32) {
+    if let Some(contacts) = collisions.get(e1, e2) {
+        let first = contacts.manifolds.iter().flat_map(|m| m.contacts.iter()).next();
+        if let (Some(data), Ok(t)) = (first, transform_q.get(contacts.entity1)) {
+            return (t.translation + t.rotation * data.point1, contacts.total_normal_impulse);
+        }
+    }
+    let midpoint = match (transform_q.get(e1), transform_q.get(e2)) {
+        (Ok(t1), Ok(t2)) => (t1.translation + t2.translation) * 0.5,
+        (Ok(t), _) | (_, Ok(t)) => t.translation,
+        _ => Vec3::ZERO
+    };
+    (midpoint, 0.)
+}
@@ -0,0 +1,284 @@
+use bevy::prelude::*;
+use avian3d::prelude::*;
+use bevy_hanabi::EffectAsset;
+
+use crate::{GameState, Health, NotReady};
+use crate::base::{AttachPoint, Base, BaseModule, ModuleKind};
+use crate::beam::BeamAssets;
+use crate::docks::spawn_dock;
+use crate::layers::dock_layers;
+use crate::mining::{Cargo, Ore, ORE_KINDS};
+use crate::turret::{spawn_turret, TurretAssets, TurretSpec, LASER_TURRET, MISSILE_TURRET};
+use crate::ui::{RegisterWidgets, ULayout, UpdateWidgets, WType, WidgetRegData, WidgetUpdateData};
+
+pub struct ConstructionPlugin;
+impl Plugin for ConstructionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BaseStock>();
+        app.init_resource::<BuildMenu>();
+        app.add_systems(Startup, spawn);
+        app.add_systems(Update, setup.run_if(in_state(GameState::Setup)));
+        app.add_systems(Update, (deliver, input, refine, update_indicators).chain().run_if(in_state(GameState::Game)));
+    }
+}
+
+// ---
+
+// Ore unloaded at the base, indexed by Ore like the cargo
+
+#[derive(Resource, Default)]
+pub struct BaseStock {
+    amounts: [f32; ORE_KINDS]
+}
+
+impl BaseStock {
+    fn can_afford(&self, cost: &[f32; ORE_KINDS]) -> bool {
+        self.amounts.iter().zip(cost).all(|(have, need)| have >= need)
+    }
+
+    fn spend(&mut self, cost: &[f32; ORE_KINDS]) {
+        for (have, need) in self.amounts.iter_mut().zip(cost) {
+            *have -= need;
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct BuildMenu {
+    open: bool
+}
+
+struct Blueprint {
+    kind: ModuleKind,
+    key: KeyCode,
+    cost: [f32; ORE_KINDS],    // ice, metal, rare
+    turret: Option<TurretSpec>
+}
+
+const BLUEPRINTS: [Blueprint; 5] = [
+    Blueprint {kind: ModuleKind::DockArm, key: KeyCode::F1, cost: [20., 40., 0.], turret: None},
+    Blueprint {kind: ModuleKind::Turret, key: KeyCode::F2, cost: [0., 30., 10.], turret: Some(LASER_TURRET)},
+    Blueprint {kind: ModuleKind::Shield, key: KeyCode::F3, cost: [10., 20., 20.], turret: None},
+    Blueprint {kind: ModuleKind::Refinery, key: KeyCode::F4, cost: [10., 50., 0.], turret: None},
+    Blueprint {kind: ModuleKind::Turret, key: KeyCode::F5, cost: [0., 40., 20.], turret: Some(MISSILE_TURRET)},
+];
+
+const MENU_OPEN: &str = "F1 Dock 20I 40M | F2 Laser turret 30M 10R | F3 Shield 10I 20M 20R | F4 Refinery 10I 50M | F5 Missile turret 40M 20R | N close";
+const MENU_CLOSED: &str = "N - build menu";
+
+#[derive(Resource)]
+pub struct ModuleAssets {
+    hull: Handle<StandardMaterial>,
+    shield: Handle<StandardMaterial>,
+    arm: Handle<Mesh>,
+    turret: Handle<Mesh>,
+    generator: Handle<Mesh>,
+    refinery: Handle<Mesh>,
+}
+
+#[derive(Component)]
+pub struct ConstructionTempMarker;
+
+const BASE_DELIVERY_RANGE: f32 = 90.;
+const DOCK_ARM_LENGTH: f32 = 12.;
+const TURRET_MOUNT_DEPTH: f32 = 6.;   // the gun sits on the outer face
+const REFINERY_RATE: f32 = 1.;        // metal per second per refinery
+const REFINERY_REPAIR: f32 = 5.;      // base health per unit of metal
+
+const I_STOCK_ICE: (&str, &str) = ("s_ice", "SI");
+const I_STOCK_METAL: (&str, &str) = ("s_metal", "SM");
+const I_STOCK_RARE: (&str, &str) = ("s_rare", "SR");
+const I_BUILD: (&str, &str) = ("build", "Build");
+
+// ---
+
+fn spawn(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    commands.spawn((NotReady, ConstructionTempMarker));
+    commands.insert_resource(ModuleAssets {
+        hull: materials.add(Color::srgb(0.5, 0.52, 0.55)),
+        shield: materials.add(StandardMaterial {
+            base_color: Color::srgb(0.3, 0.6, 1.),
+            emissive: LinearRgba::rgb(0.5, 1.5, 4.),
+            ..default()
+        }),
+        arm: meshes.add(Cuboid::new(3., 3., DOCK_ARM_LENGTH)),
+        turret: meshes.add(Cuboid::new(6., 4., TURRET_MOUNT_DEPTH)),
+        generator: meshes.add(Sphere::new(6.)),
+        refinery: meshes.add(Cuboid::new(12., 10., 12.)),
+    });
+}
+
+// ---
+
+fn setup (
+    mut commands: Commands,
+    check_q: Query<Entity, (With<NotReady>, With<ConstructionTempMarker>)>,
+    mut writer: EventWriter<RegisterWidgets>,
+) {
+    let Ok(e) = check_q.get_single() else {
+        return;
+    };
+    let mut widgets: Vec<WidgetRegData> = [I_STOCK_ICE, I_STOCK_METAL, I_STOCK_RARE].into_iter().map(|(key, label)| WidgetRegData {
+        key,
+        label,
+        parent: ULayout::SidebarLeft,
+        wtype: WType::Float,
+        image: None,
+        start: 1,
+        span: 2,
+        default: Some(0.)
+    }).collect();
+    widgets.push(WidgetRegData {
+        key: I_BUILD.0,
+        label: I_BUILD.1,
+        parent: ULayout::Content,
+        wtype: WType::Text,
+        image: None,
+        start: 1,
+        span: 1,
+        default: None
+    });
+    writer.send(RegisterWidgets(widgets));
+    commands.entity(e).despawn();
+}
+
+// ---
+
+// A ship close to the base hands over everything in its hold
+
+fn deliver(
+    base_q: Query<&GlobalTransform, With<Base>>,
+    mut drone_q: Query<(&Transform, &mut Cargo)>,
+    mut stock: ResMut<BaseStock>,
+) {
+    let Ok(base_t) = base_q.get_single() else {
+        return;
+    };
+    for (t, mut cargo) in drone_q.iter_mut() {
+        if cargo.load() <= 0. || t.translation.distance(base_t.translation()) > BASE_DELIVERY_RANGE {
+            continue;
+        }
+        for (have, v) in stock.amounts.iter_mut().zip(cargo.unload()) {
+            *have += v;
+        }
+    }
+}
+
+// ---
+
+// Each module goes to the first free attachment point
+
+fn input(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut menu: ResMut<BuildMenu>,
+    mut stock: ResMut<BaseStock>,
+    base_q: Query<(Entity, &GlobalTransform), With<Base>>,
+    mut points_q: Query<(&mut AttachPoint, &Transform)>,
+    assets: Res<ModuleAssets>,
+    turret_assets: Res<TurretAssets>,
+    beam_assets: Res<BeamAssets>,
+    mut effects: ResMut<Assets<EffectAsset>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    if keys.just_pressed(KeyCode::KeyN) {
+        menu.open = !menu.open;
+    }
+    if !menu.open {
+        return;
+    }
+    let Some(blueprint) = BLUEPRINTS.iter().find(|b| keys.just_pressed(b.key)) else {
+        return;
+    };
+    let Ok((base_e, base_t)) = base_q.get_single() else {
+        return;
+    };
+    if !stock.can_afford(&blueprint.cost) {
+        return;
+    }
+    let Some((mut point, point_t)) = points_q.iter_mut().find(|(p, _)| !p.occupied) else {
+        return;
+    };
+
+    let (mesh, material, collider, length) = match blueprint.kind {
+        ModuleKind::DockArm => (assets.arm.clone(), assets.hull.clone(), Collider::cuboid(3., 3., DOCK_ARM_LENGTH), DOCK_ARM_LENGTH),
+        ModuleKind::Turret => (assets.turret.clone(), assets.hull.clone(), Collider::cuboid(6., 4., TURRET_MOUNT_DEPTH), TURRET_MOUNT_DEPTH),
+        ModuleKind::Shield => (assets.generator.clone(), assets.shield.clone(), Collider::sphere(6.), 12.),
+        ModuleKind::Refinery => (assets.refinery.clone(), assets.hull.clone(), Collider::cuboid(12., 10., 12.), 12.),
+        _ => return
+    };
+    stock.spend(&blueprint.cost);
+    point.occupied = true;
+
+    // modules stick out of the point along its forward
+    let transform = point_t.mul_transform(Transform::from_translation(Vec3::NEG_Z * length / 2.));
+    let module = commands.spawn((
+        PbrBundle {
+            mesh,
+            material,
+            transform,
+            ..default()
+        },
+        BaseModule(blueprint.kind),
+        collider,
+        dock_layers(),
+    )).id();
+    commands.entity(base_e).add_child(module);
+
+    if blueprint.kind == ModuleKind::DockArm {
+        let tip = point_t.transform_point(Vec3::NEG_Z * (length + 6.));
+        spawn_dock(&mut commands, &mut effects, &mut materials, &mut meshes, base_t.transform_point(tip));
+    }
+
+    if let Some(spec) = blueprint.turret {
+        let head = spawn_turret(&mut commands, &turret_assets, &beam_assets, &mut materials, Transform::from_translation(Vec3::NEG_Z * (length / 2. + 2.)), &spec);
+        commands.entity(module).add_child(head);
+    }
+}
+
+// ---
+
+fn refine(
+    modules_q: Query<&BaseModule>,
+    mut base_q: Query<&mut Health, With<Base>>,
+    mut stock: ResMut<BaseStock>,
+    time: Res<Time>
+) {
+    let Ok(mut health) = base_q.get_single_mut() else {
+        return;
+    };
+    let refineries = modules_q.iter().filter(|m| m.0 == ModuleKind::Refinery).count();
+    if refineries == 0 || health.percent() >= 1. {
+        return;
+    }
+    // read first, the stock only changes when metal is really used
+    let metal = stock.amounts[Ore::Metal as usize];
+    let used = f32::min(REFINERY_RATE * refineries as f32 * time.delta_seconds(), metal);
+    if used > 0. {
+        stock.amounts[Ore::Metal as usize] -= used;
+        health.heal(used * REFINERY_REPAIR);
+    }
+}
+
+// ---
+
+fn update_indicators(
+    stock: Res<BaseStock>,
+    menu: Res<BuildMenu>,
+    mut writer: EventWriter<UpdateWidgets>
+) {
+    if !stock.is_changed() && !menu.is_changed() {
+        return;
+    }
+    writer.send(UpdateWidgets(vec![
+        WidgetUpdateData::from_key_value(I_STOCK_ICE.0, stock.amounts[Ore::Ice as usize]),
+        WidgetUpdateData::from_key_value(I_STOCK_METAL.0, stock.amounts[Ore::Metal as usize]),
+        WidgetUpdateData::from_key_value(I_STOCK_RARE.0, stock.amounts[Ore::Rare as usize]),
+        WidgetUpdateData::from_key_text(I_BUILD.0, if menu.open {MENU_OPEN} else {MENU_CLOSED})
+    ]));
+}
@@ -0,0 +1,97 @@
+use bevy::prelude::*;
+use crate::{DespawnOnDeath, Health};
+use crate::effect_pool::{EffectKind, PlayEffect};
+
+pub struct DamagePlugin;
+impl Plugin for DamagePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, apply.in_set(DamageSystems).run_if(on_event::<DamageEvent>()));
+        app.add_systems(Update, regenerate.before(DamageSystems));
+        app.add_systems(Update, despawn.after(DamageSystems).run_if(on_event::<Died>()));
+        app.add_event::<DamageEvent>();
+        app.add_event::<Died>();
+    }
+}
+
+// ---
+
+// Died consumers run after it, so they all see the dead entity before it's despawned
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DamageSystems;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DamageKind {
+    Laser,
+    Explosion,
+    Kinetic,
+    Impact,
+    Gravity,
+}
+
+// Every weapon reports damage here instead of touching Health itself
+
+#[derive(Event, Debug)]
+pub struct DamageEvent {
+    pub source: Entity,
+    pub target: Entity,
+    pub amount: f32,
+    pub kind: DamageKind,
+    pub point: Vec3
+}
+
+// Sent once, by the hit that took Health to zero
+
+#[derive(Event, Debug)]
+pub struct Died {
+    pub entity: Entity,
+    pub source: Entity,
+    pub kind: DamageKind,
+    pub point: Vec3
+}
+
+// ---
+
+fn apply(
+    mut reader: EventReader<DamageEvent>,
+    mut health_q: Query<&mut Health>,
+    mut writer: EventWriter<Died>
+) {
+    for ev in reader.read() {
+        let Ok(mut h) = health_q.get_mut(ev.target) else {
+            continue;
+        };
+        if h.damage(ev.amount) {
+            writer.send(Died {entity: ev.target, source: ev.source, kind: ev.kind, point: ev.point});
+        }
+    }
+}
+
+// ---
+
+fn regenerate(
+    mut health_q: Query<&mut Health>,
+    time: Res<Time>
+) {
+    for mut h in health_q.iter_mut() {
+        if let Some(regen) = h.regen() {
+            h.heal(regen * time.delta_seconds());
+        }
+    }
+}
+
+// ---
+
+fn despawn(
+    mut commands: Commands,
+    mut reader: EventReader<Died>,
+    despawn_q: Query<&Transform, With<DespawnOnDeath>>,
+    mut fx_writer: EventWriter<PlayEffect>,
+) {
+    for ev in reader.read() {
+        if let Ok(t) = despawn_q.get(ev.entity) {
+            fx_writer.send(PlayEffect(EffectKind::Blast, t.translation));
+            commands.entity(ev.entity).despawn_recursive();
+        }
+    }
+}
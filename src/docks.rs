@@ -1,5 +1,6 @@
 use std::f32::consts::PI;
 use std::time::Duration;
+use std::collections::VecDeque;
 use crate::drone::NeedService;
 use crate::drone::UnderService;
 use bevy::prelude::*;
@@ -8,13 +9,20 @@ use bevy_hanabi::EffectProperties;
 use bevy_hanabi::{ParticleEffect, ParticleEffectBundle, EffectAsset};
 use avian3d::prelude::*;
 use crate::effects::*;
+use crate::{DespawnOnDeath, GameState, Health, NotReady, Target};
+use crate::collision_routing::DroneDockContact;
+use crate::layers::dock_layers;
+use crate::ui::{RegisterWidgets, ULayout, UpdateWidgets, WType, WidgetRegData, WidgetUpdateData};
 
 pub struct DocksPlugin;
 impl Plugin for DocksPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, spawn);
-        app.add_systems(Update, scan.run_if(on_timer(Duration::from_secs(1))));
+        app.add_systems(Update, setup.run_if(in_state(GameState::Setup)));
+        app.add_systems(Update, (release_lost, reserve, scan).chain().run_if(on_timer(Duration::from_secs(1))));
         app.add_systems(Update, (service, service_free).chain());
+        app.add_systems(Update, contact.run_if(on_event::<DroneDockContact>()));
+        app.add_systems(Update, update_indicators.run_if(in_state(GameState::Game)));
     }
 }
 
@@ -26,10 +34,33 @@ pub struct Dock;
 #[derive(Component)]
 pub struct Aura(Entity);
 
+// One ship per dock: the dock holds the ship it is reserved for as its Client,
+// the ship holds the Reservation back, so either side is found without a search
+
+#[derive(Component)]
+pub struct Reservation(pub Entity);
+
+// Ships waiting for a busy dock, the first in line gets it next
+
+#[derive(Component, Default)]
+pub struct DockQueue(VecDeque<Entity>);
+
+#[derive(Component)]
+pub struct Queued(pub Entity);
+
+#[derive(Component)]
+pub struct DocksTempMarker;
+
 // ---
 
 const DOCKS_COUNT: usize = 16;
 const DOCKS_RADIUS: f32 = 600.;
+const DOCKS_REARM_RATE: f32 = 0.5;   // missiles per second
+const DOCK_HEALTH: f32 = 200.;
+const DOCK_SERVICE_RANGE: f32 = 50.;
+const DOCK_REDIRECT_DETOUR: f32 = 300.;  // how much farther than the nearest dock a free one may be
+
+const I_DOCK_QUEUE: (&str, &str) = ("dock_q", "DQ");
 
 fn spawn (
     mut commands: Commands,
@@ -37,100 +68,322 @@ fn spawn (
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut meshes: ResMut<Assets<Mesh>>
 ) {
+    commands.spawn((NotReady, DocksTempMarker));
     let angle_step = 2. * PI / DOCKS_COUNT as f32; 
     let mut angle: f32 = 0.;
     for _i in 0 .. DOCKS_COUNT {
         let dock_position = Vec3::new(angle.cos() * DOCKS_RADIUS, 100., angle.sin() * DOCKS_RADIUS);
         angle += angle_step;
-        
-
-        let mut aura_id = Entity::PLACEHOLDER;
-    
-        let dock_id = commands.spawn((
-            PbrBundle {
-                mesh: meshes.add(Sphere::new(5.)),
-                material: materials.add(StandardMaterial {
-                    base_color: Color::srgba(0., 0., 0., 0.5),
-                    reflectance: 1.,
-                    ..default()
-                }),
-                transform: Transform::from_translation(dock_position),
-                ..default()
-            },
-            Dock,
-            RigidBody::Static,
-            Collider::sphere(5.),
-        ))
-        .with_children(|p| {
-            aura_id = p.spawn(
-                ParticleEffectBundle {
-                    effect: ParticleEffect::new(effects.add(dock_aura())),
-                    ..default()
-                }
-            ).id();
-        }).id()
-        ;    
-        commands.entity(dock_id).insert(Aura(aura_id));
+        spawn_dock(&mut commands, &mut effects, &mut materials, &mut meshes, dock_position);
     }
 
 }
 
-use crate::drone::DroneEvent;
+// ---
+
+pub fn spawn_dock(
+    commands: &mut Commands,
+    effects: &mut Assets<EffectAsset>,
+    materials: &mut Assets<StandardMaterial>,
+    meshes: &mut Assets<Mesh>,
+    dock_position: Vec3
+) -> Entity {
+    let mut aura_id = Entity::PLACEHOLDER;
+
+    let dock_id = commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Sphere::new(5.)),
+            material: materials.add(StandardMaterial {
+                base_color: Color::srgba(0., 0., 0., 0.5),
+                reflectance: 1.,
+                ..default()
+            }),
+            transform: Transform::from_translation(dock_position),
+            ..default()
+        },
+        Dock,
+        DockQueue::default(),
+        Health::new(DOCK_HEALTH),
+        DespawnOnDeath,
+        RigidBody::Static,
+        Collider::sphere(5.),
+        dock_layers(),
+    ))
+    .with_children(|p| {
+        aura_id = p.spawn(
+            ParticleEffectBundle {
+                effect: ParticleEffect::new(effects.add(dock_aura())),
+                ..default()
+            }
+        ).id();
+    }).id()
+    ;    
+    commands.entity(dock_id).insert(Aura(aura_id));
+    dock_id
+}
+
+// ---
+
+fn setup (
+    mut commands: Commands,
+    check_q: Query<Entity, (With<NotReady>, With<DocksTempMarker>)>,
+    mut writer: EventWriter<RegisterWidgets>,
+) {
+    let Ok(e) = check_q.get_single() else {
+        return;
+    };
+    writer.send(
+        RegisterWidgets(
+            vec![
+                WidgetRegData {
+                    key: I_DOCK_QUEUE.0,
+                    label: I_DOCK_QUEUE.1,
+                    parent: ULayout::SidebarLeft,
+                    wtype: WType::Integer,
+                    image: None,
+                    start: 1,
+                    span: 2,
+                    default: Some(0.)
+                },
+            ]
+        )
+    );
+    commands.entity(e).despawn();
+}
+
+use crate::drone::{DroneEvent, Manual};
 
 #[derive(Component)]
 pub struct Client(pub Entity);
 
 // ---
 
+// A ship in need takes the nearest free dock unless that is a long detour,
+// otherwise it waits in line at the nearest dock. Ships in line keep looking for a free dock
+
+fn reserve(
+    mut commands: Commands,
+    ships_q: Query<(Entity, &Transform, Option<&Queued>, Has<Manual>), (With<NeedService>, Without<Reservation>)>,
+    mut docks_q: Query<(Entity, &Transform, &mut DockQueue, Has<Client>), With<Dock>>,
+    target_q: Query<Entity, With<Target>>,
+) {
+    // docks reserved in this pass, their Client is not inserted yet
+    let mut taken = Vec::new();
+
+    for (ship, st, queued, manual) in ships_q.iter() {
+        // the line of a destroyed dock is no line
+        let queued = queued.filter(|q| docks_q.contains(q.0));
+        let distance = |dt: &Transform| dt.translation.distance(st.translation);
+        let Some((nearest, nearest_distance)) = docks_q.iter()
+            .map(|(de, dt, ..)| (de, distance(dt)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+        else {
+            continue;
+        };
+        let free = docks_q.iter()
+            .filter(|(de, .., busy)| !busy && !taken.contains(de))
+            .map(|(de, dt, ..)| (de, distance(dt)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .filter(|(_, d)| *d <= nearest_distance + DOCK_REDIRECT_DETOUR);
+
+        let dock = match (free, queued) {
+            (Some((dock, _)), _) => {
+                if let Some(q) = queued {
+                    if let Ok((.., mut queue, _)) = docks_q.get_mut(q.0) {
+                        queue.0.retain(|e| *e != ship);
+                    }
+                    commands.entity(ship).remove::<Queued>();
+                }
+                taken.push(dock);
+                commands.entity(dock).insert(Client(ship));
+                commands.entity(ship).insert(Reservation(dock));
+                dock
+            },
+            (None, None) => {
+                if let Ok((.., mut queue, _)) = docks_q.get_mut(nearest) {
+                    queue.0.push_back(ship);
+                }
+                commands.entity(ship).insert(Queued(nearest));
+                nearest
+            },
+            (None, Some(_)) => continue
+        };
+
+        // the dock becomes the player's Target, the autopilot (V) follows the Target only
+
+        if manual {
+            point_to(&mut commands, dock, &target_q);
+        }
+    }
+}
+
+// ---
+
 fn scan(
-    clients_q: Query<(&Transform, Entity),  With<NeedService>>,
-    docks_q: Query<(&Transform, Entity), (With<Dock>, Without<NeedService>)>,
+    clients_q: Query<(Entity, &Transform, &Reservation), With<NeedService>>,
+    docks_q: Query<&Transform, With<Dock>>,
     mut ev_info: EventWriter<DroneEvent>,
-    mut commands: Commands,
 ) {
-    for (client_transform, client_entity) in clients_q.iter() {
-            for (dock_transform, dock_entity )  in docks_q.iter() {
-            if dock_transform.translation.distance(client_transform.translation) < 50. {
-                ev_info.send(DroneEvent::Service(client_entity));
-                commands.entity(dock_entity).insert(Client(client_entity));
-            }
+    for (client_entity, client_transform, reservation) in clients_q.iter() {
+        let Ok(dock_transform) = docks_q.get(reservation.0) else {
+            continue;
+        };
+        if dock_transform.translation.distance(client_transform.translation) < DOCK_SERVICE_RANGE {
+            ev_info.send(DroneEvent::Service(client_entity));
         }
     }
 }
 
 // ---
 
+// Touching the reserved dock docks right away, without waiting for the scan
+
+fn contact(
+    mut contact_events: EventReader<DroneDockContact>,
+    clients_q: Query<&Reservation, With<NeedService>>,
+    mut ev_info: EventWriter<DroneEvent>,
+) {
+    for ev in contact_events.read() {
+        if clients_q.get(ev.drone).is_ok_and(|r| r.0 == ev.dock) {
+            ev_info.send(DroneEvent::Service(ev.drone));
+        }
+    }
+}
+
+// ---
+
+// A reserved dock is lit as busy while the ship is on its way, supplies go only to a docked ship
+
 fn service(
-    docks_q: Query<(&Client, &Aura), (With<Dock>, Without<UnderService>)>,
+    docks_q: Query<(&Client, &Aura), With<Dock>>,
+    docked_q: Query<(), With<UnderService>>,
     mut effect_q: Query<&mut EffectProperties>,
     mut ev_writer: EventWriter<DroneEvent>,
+    time: Res<Time>
 ) {
     for (client_e, aura_e ) in docks_q.iter() {
         if let Ok(props) = effect_q.get_mut(aura_e.0) {
             let color = LinearRgba::rgb(10.0, 6.0, 0.0).as_u32();
             EffectProperties::set_if_changed(props, "p_color", color.into());
         }
+        if !docked_q.contains(client_e.0) {
+            continue;
+        }
         ev_writer.send(DroneEvent::SupplyFluel((client_e.0, 1.0)));
+        ev_writer.send(DroneEvent::SupplyMissiles((client_e.0, DOCKS_REARM_RATE * time.delta_seconds())));
     }
 }
 
 // ---
 
 fn service_free(
-    mut removals: RemovedComponents<UnderService> ,
-    docks_q: Query<(&Client, &Aura, Entity)>, 
+    mut removals: RemovedComponents<UnderService>,
+    reservations_q: Query<&Reservation>,
+    mut docks_q: Query<(&Aura, &mut DockQueue)>,
+    waiting_q: Query<Has<Manual>, With<Queued>>,
+    target_q: Query<Entity, With<Target>>,
     mut effect_q: Query<&mut EffectProperties>,
     mut commands: Commands
 ) {
-    for client_en in  removals.read() {
-        for (client, aura, dock_ent) in docks_q.iter() {
-            if client_en == client.0 {
-                if let Ok(props) = effect_q.get_mut(aura.0) {
-                    let color = LinearRgba::rgb(0.0, 14.0, 4.0).as_u32();
-                    EffectProperties::set_if_changed(props, "p_color", color.into());
-                    commands.entity(dock_ent).remove::<Client>();
-                }
-            }
+    for client_en in removals.read() {
+        let Ok(reservation) = reservations_q.get(client_en) else {
+            continue;
+        };
+        commands.entity(client_en).remove::<Reservation>();
+        if let Ok((aura, mut queue)) = docks_q.get_mut(reservation.0) {
+            hand_over(&mut commands, reservation.0, &mut queue, aura, &waiting_q, &target_q, &mut effect_q);
+        }
+    }
+}
+
+// ---
+
+// Ships gone while holding a reservation or a place in line, and ships whose dock is gone
+
+fn release_lost(
+    mut commands: Commands,
+    mut docks_q: Query<(Entity, Option<&Client>, &Aura, &mut DockQueue)>,
+    reserved_q: Query<(Entity, &Reservation)>,
+    waiting_q: Query<Has<Manual>, With<Queued>>,
+    target_q: Query<Entity, With<Target>>,
+    mut effect_q: Query<&mut EffectProperties>,
+) {
+    for (ship, reservation) in reserved_q.iter() {
+        if !docks_q.contains(reservation.0) {
+            // a ship docked there is let go, it asks for service again if still short of fluel
+            commands.entity(ship).remove::<(Reservation, UnderService)>();
+        }
+    }
+    for (dock, client, aura, mut queue) in docks_q.iter_mut() {
+        queue.0.retain(|e| waiting_q.contains(*e));
+        if client.is_some_and(|c| !reserved_q.contains(c.0)) {
+            hand_over(&mut commands, dock, &mut queue, aura, &waiting_q, &target_q, &mut effect_q);
+        }
+    }
+}
+
+// ---
+
+// The dock goes to the first ship in line still around, or becomes free
+
+fn hand_over(
+    commands: &mut Commands,
+    dock: Entity,
+    queue: &mut DockQueue,
+    aura: &Aura,
+    waiting_q: &Query<Has<Manual>, With<Queued>>,
+    target_q: &Query<Entity, With<Target>>,
+    effect_q: &mut Query<&mut EffectProperties>
+) {
+    while let Some(next) = queue.0.pop_front() {
+        let Ok(manual) = waiting_q.get(next) else {
+            continue;
+        };
+        commands.entity(dock).insert(Client(next));
+        commands.entity(next).remove::<Queued>().insert(Reservation(dock));
+        if manual {
+            point_to(commands, dock, target_q);
         }
+        return;
     }
-}
\ No newline at end of file
+    commands.entity(dock).remove::<Client>();
+    if let Ok(props) = effect_q.get_mut(aura.0) {
+        let color = LinearRgba::rgb(0.0, 14.0, 4.0).as_u32();
+        EffectProperties::set_if_changed(props, "p_color", color.into());
+    }
+}
+
+// ---
+
+fn point_to(commands: &mut Commands, dock: Entity, target_q: &Query<Entity, With<Target>>) {
+    if let Ok(old_target) = target_q.get_single() {
+        commands.entity(old_target).remove::<Target>();
+    }
+    commands.entity(dock).insert(Target);
+}
+
+// ---
+
+// Place in line, 0 when not waiting. Sent only when it moves, the widget starts at 0
+
+fn update_indicators(
+    drone_q: Query<(Entity, Option<&Queued>), With<Manual>>,
+    docks_q: Query<&DockQueue>,
+    mut last: Local<usize>,
+    mut writer: EventWriter<UpdateWidgets>
+) {
+    let Ok((de, queued)) = drone_q.get_single() else {
+        return;
+    };
+    let position = queued
+        .and_then(|q| docks_q.get(q.0).ok())
+        .and_then(|queue| queue.0.iter().position(|e| *e == de))
+        .map_or(0, |i| i + 1);
+    if position == *last {
+        return;
+    }
+    *last = position;
+    writer.send(UpdateWidgets(vec![
+        WidgetUpdateData::from_key_value(I_DOCK_QUEUE.0, position as f32)
+    ]));
+}
@@ -5,9 +5,13 @@ use bevy_hanabi::prelude::*;
 use crate::effects::{engine, steer, ship_aura};
 use crate::camera::Focus;
 use crate::ui::{RegisterWidgets, ULayout, UpdateWidgets, WidgetRegData, WidgetUpdateData, WType};
-use crate::Target;
+use crate::{GameOver, Health, Target};
 use crate::GameState;
-use crate::docks::{Client, Dock};
+use crate::missile::Missiles;
+use crate::collision_routing::DroneAsteroidHit;
+use crate::layers::ship_layers;
+use crate::damage::{DamageEvent, DamageKind, DamageSystems, Died};
+use crate::effect_pool::{EffectKind, PlayEffect};
 use bevy::color::palettes::css::*;
 
 // ---
@@ -25,6 +29,8 @@ impl Plugin for DronePlugin {
             check_state,
             update_indicators
         ).run_if(in_state(GameState::Game)));
+        app.add_systems(Update, impact.run_if(on_event::<DroneAsteroidHit>()));
+        app.add_systems(Update, lost.after(DamageSystems).run_if(on_event::<Died>()));
         app.add_event::<DroneEvent>();
         app.add_event::<DroneControl>();
     }
@@ -53,6 +59,8 @@ const I_DIST_XZ: (&str, &str) = ("d_xz","Dist XZ");
 const I_DIST_Y: (&str, &str) = ("d_y","Dist Y");
 const I_FLUEL: (&str, &str) = ("fluel","Fluel");
 const I_DIRECTION_KEY: &str = "dir";
+const I_TARGET_HEALTH: (&str, &str) = ("t_hp", "T");
+const I_HULL: (&str, &str) = ("hull", "Hull");
 
 // - UI Indicators 
 
@@ -122,7 +130,8 @@ impl Fluel{
 #[derive(Event, PartialEq)]
 pub enum DroneEvent {
     Service(Entity),
-    SupplyFluel((Entity, f32))
+    SupplyFluel((Entity, f32)),
+    SupplyMissiles((Entity, f32))
 }
 
 #[derive(Event, PartialEq)]
@@ -131,6 +140,9 @@ pub struct DroneControl(pub Entity, pub u8, pub f32);
 // - Events =======================================================================================================
 
 const LINEAR_DAMPING_DEFAULT: f32 = 0.01;  
+const DRONE_HEALTH: f32 = 100.;
+const DRONE_REGEN: f32 = 0.5;
+const DRONE_IMPACT_DAMAGE: f32 = 0.2;   // per unit of impulse
 
 // ---
 
@@ -149,8 +161,10 @@ fn spawn(
         Drone,
         Focus,
         Fluel(FLUEL_CAPACITY),
+        Health::new(DRONE_HEALTH).with_regen(DRONE_REGEN),
         RigidBody::Dynamic,
         Collider::cuboid(1.25, 0.25, 2.25),
+        ship_layers(),
         GravityScale(0.),
         ExternalImpulse::new(Vec3::ZERO),
         Multiplier {linear: 10., angular: 1.},
@@ -289,6 +303,26 @@ fn setup_ui(
                             image: Some(asset.load("images/arrow.png")),
                             default: None
                         },
+                        WidgetRegData {
+                            key: I_TARGET_HEALTH.0,
+                            parent: ULayout::Header,
+                            wtype: WType::Integer,
+                            label: I_TARGET_HEALTH.1,
+                            start: 10,
+                            span: 1,
+                            image: None,
+                            default: None
+                        },
+                        WidgetRegData {
+                            key: I_HULL.0,
+                            parent: ULayout::SidebarLeft,
+                            wtype: WType::Integer,
+                            label: I_HULL.1,
+                            start: 1,
+                            span: 2,
+                            image: None,
+                            default: Some(100.)
+                        },
 
                     ]
                 )
@@ -425,7 +459,7 @@ fn read_events (
     mut reader : EventReader<DroneEvent>,
     mut ev_writer: EventWriter<DroneControl>,
     mut commands: Commands,
-    mut drone_q: Query<&mut Fluel>
+    mut drone_q: Query<(&mut Fluel, Option<&Missiles>)>
 ) {
     for e in reader.read() {
         match e {
@@ -436,12 +470,14 @@ fn read_events (
                 ev_writer.send(DroneControl(*e, 3, 10.));
             },
             DroneEvent::SupplyFluel {0: (e,v) } => {
-                if let Ok(mut fluel ) = drone_q.get_mut(*e) {
-                    if fluel.gain(*v) {
+                if let Ok((mut fluel, om)) = drone_q.get_mut(*e) {
+                    let armed = if let Some(m) = om {m.is_full()} else {true};
+                    if fluel.gain(*v) && armed {
                         commands.entity(*e).remove::<UnderService>();
                     }
                 }
-            }
+            },
+            DroneEvent::SupplyMissiles(_) => ()
         }
     }
 }
@@ -450,30 +486,13 @@ fn read_events (
 
 fn check_state (
     mut commands: Commands,
-    drone_q: Query<(Entity, &Fluel, &Transform) , With<Drone>> ,
-    docks_q:Query<(&Transform, Entity), (With<Dock>, Without<Client>)>,
-    target_q: Query<Entity, With<Target>>  
+    drone_q: Query<(Entity, &Fluel), (With<Drone>, Without<NeedService>, Without<UnderService>)>,
 ) {
-    if let Ok((drone_e, fluel, drone_trans))  = drone_q.get_single() {
+    // the dock is reserved and pointed to by the docks plugin
+
+    for (drone_e, fluel) in drone_q.iter() {
         if fluel.limit() {
             commands.entity(drone_e).insert(NeedService);
-            let mut candidate = Entity::PLACEHOLDER;
-            let mut min_distance =  f32::MAX;
-            for (dock_trans, dock_e) in docks_q.iter() {
-                let distance = drone_trans.translation.distance_squared(dock_trans.translation);
-                if  distance < min_distance {
-                    min_distance = distance;
-                    candidate = dock_e;
-                }
-            } 
-
-            if candidate != Entity::PLACEHOLDER {
-                if let Ok(old_target) = target_q.get_single() {
-                    commands.entity(old_target).remove::<Target>();
-                    commands.entity(candidate).insert(Target);
-                }
-            }
-            
         }
     }
 }
@@ -481,17 +500,17 @@ fn check_state (
 // ---
 
 fn update_indicators(
-    drone_q: Query<(&LinearVelocity, &Transform, &Fluel), (With<Drone>, With<Focus>,  Without<Target>)>,
-    target_q: Query<&Transform, (With<Target>, Without<Drone>, Without<Focus>)>,
+    drone_q: Query<(&LinearVelocity, &Transform, &Fluel, &Health), (With<Drone>, With<Focus>,  Without<Target>)>,
+    target_q: Query<(&Transform, Option<&Health>), (With<Target>, Without<Drone>, Without<Focus>)>,
     mut writer: EventWriter<UpdateWidgets>
 ) {
-    let target_translation = if let Ok(target_transform) =  target_q.get_single()  {
-        target_transform.translation
+    let (target_translation, target_health) = if let Ok((target_transform, oh)) =  target_q.get_single()  {
+        (target_transform.translation, if let Some(h) = oh {h.percent()} else {0.})
     } else {
-        Vec3::ZERO
+        (Vec3::ZERO, 0.)
     };
 
-    let Ok((v, drone_transform, fluel)) = drone_q.get_single() else {
+    let Ok((v, drone_transform, fluel, health)) = drone_q.get_single() else {
         return;
     };
     let to_target = target_translation - drone_transform.translation;
@@ -509,6 +528,48 @@ fn update_indicators(
             WidgetUpdateData::from_key_value(I_DIST_Y.0, drone_transform.translation.y -  target_translation.y),
             WidgetUpdateData::from_key_value_color(I_FLUEL.0, fluel.get(), if fluel.limit() {ORANGE_RED.into()} else {YELLOW_GREEN.into()}),
             WidgetUpdateData::from_key_value(I_DIRECTION_KEY, angle),
+            WidgetUpdateData::from_key_value(I_TARGET_HEALTH.0, (target_health * 100.).round()),
+            WidgetUpdateData::from_key_value_color(I_HULL.0, (health.percent() * 100.).round(), if health.percent() < 0.3 {ORANGE_RED.into()} else {YELLOW_GREEN.into()}),
         ]
     ));
 }
+
+// ---
+
+fn impact(
+    mut hit_events: EventReader<DroneAsteroidHit>,
+    mut damage_writer: EventWriter<DamageEvent>,
+) {
+    for hit in hit_events.read() {
+        damage_writer.send(DamageEvent {
+            source: hit.asteroid,
+            target: hit.drone,
+            amount: hit.impulse * DRONE_IMPACT_DAMAGE,
+            kind: DamageKind::Impact,
+            point: hit.point
+        });
+    }
+}
+
+// ---
+
+// No spare ships, losing the player's drone ends the game like losing the base
+
+fn lost(
+    mut commands: Commands,
+    mut reader: EventReader<Died>,
+    drone_q: Query<&Transform, (With<Drone>, With<Manual>)>,
+    mut fx_writer: EventWriter<PlayEffect>,
+    mut next: ResMut<NextState<GameState>>,
+    mut physics_time: ResMut<Time<Physics>>,
+) {
+    for ev in reader.read() {
+        let Ok(t) = drone_q.get(ev.entity) else {
+            continue;
+        };
+        fx_writer.send(PlayEffect(EffectKind::Blast, t.translation));
+        commands.insert_resource(GameOver("Ship lost. Game over"));
+        physics_time.pause();
+        next.set(GameState::Over);
+    }
+}
@@ -0,0 +1,108 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_hanabi::prelude::*;
+use crate::effects::{blast, small_blast, debris};
+
+pub struct EffectPoolPlugin;
+impl Plugin for EffectPoolPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EffectPoolConfig>();
+        app.add_systems(Startup, spawn);
+        app.add_systems(Update, play.run_if(on_event::<PlayEffect>()));
+        app.add_event::<PlayEffect>();
+    }
+}
+
+// ---
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum EffectKind {
+    Blast,
+    Impact,
+    Debris
+}
+
+// Insert before the plugin to override the pool sizes
+
+#[derive(Resource)]
+pub struct EffectPoolConfig {
+    pub blast: usize,
+    pub impact: usize,
+    pub debris: usize
+}
+
+impl Default for EffectPoolConfig {
+    fn default() -> Self {
+        Self {blast: 8, impact: 16, debris: 8}
+    }
+}
+
+#[derive(Event)]
+pub struct PlayEffect(pub EffectKind, pub Vec3);
+
+#[derive(Component)]
+pub struct Pooled;
+
+// Emitters are handed out round robin, so when every one is busy the oldest is recycled
+
+struct EffectPool {
+    emitters: Vec<Entity>,
+    next: usize
+}
+
+impl EffectPool {
+    fn acquire(&mut self) -> Option<Entity> {
+        if self.emitters.is_empty() {
+            return None;
+        }
+        let e = self.emitters[self.next];
+        self.next = (self.next + 1) % self.emitters.len();
+        Some(e)
+    }
+}
+
+#[derive(Resource)]
+pub struct EffectPools(HashMap<EffectKind, EffectPool>);
+
+// ---
+
+fn spawn(
+    mut commands: Commands,
+    mut effects: ResMut<Assets<EffectAsset>>,
+    config: Res<EffectPoolConfig>
+) {
+    let mut pools = HashMap::new();
+    for (kind, size, asset) in [
+        (EffectKind::Blast, config.blast, blast()),
+        (EffectKind::Impact, config.impact, small_blast()),
+        (EffectKind::Debris, config.debris, debris()),
+    ] {
+        let handle = effects.add(asset);
+        let emitters = (0 .. size).map(|_| {
+            commands.spawn((
+                ParticleEffectBundle::new(handle.clone()),
+                Pooled
+            )).id()
+        }).collect();
+        pools.insert(kind, EffectPool {emitters, next: 0});
+    }
+    commands.insert_resource(EffectPools(pools));
+}
+
+// ---
+
+fn play(
+    mut ev_reader: EventReader<PlayEffect>,
+    mut pools: ResMut<EffectPools>,
+    mut spawner_q: Query<(&mut Transform, &mut EffectSpawner), With<Pooled>>
+) {
+    for PlayEffect(kind, position) in ev_reader.read() {
+        let Some(e) = pools.0.get_mut(kind).and_then(|p| p.acquire()) else {
+            continue;
+        };
+        if let Ok((mut t, mut s)) = spawner_q.get_mut(e) {
+            t.translation = *position;
+            s.reset();
+        }
+    }
+}
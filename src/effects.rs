@@ -436,3 +436,51 @@ pub fn laser() ->EffectAsset {
     .render(size_modifier)
 }
 
+
+// ============================================================================================================================
+
+pub fn debris() ->EffectAsset {
+    let mut gradient = Gradient::new();
+    gradient.add_key(0.0, Vec4::new(6., 3., 1., 1.));
+    gradient.add_key(0.3, Vec4::new(1., 0.6, 0.3, 0.8));
+    gradient.add_key(1., Vec4::new(0.2, 0.2, 0.2, 0.));
+    
+    let color_modifier =  ColorOverLifetimeModifier {
+        gradient: gradient,
+    };
+    let size_modifier = SetSizeModifier {
+        size: Vec2::new(0.3, 0.3).into(),
+        ..default()
+    };
+
+    let writer = ExprWriter::new();
+
+    let age = writer.lit(0.).uniform(writer.lit(0.05)).expr();
+    let init_age = SetAttributeModifier::new(Attribute::AGE, age);
+    let lifetime = writer.lit(0.8).uniform(writer.lit(1.5)).expr();
+    let init_lifetime = SetAttributeModifier::new(Attribute::LIFETIME, lifetime);
+
+    let init_pos = SetPositionSphereModifier {
+        center: writer.lit(Vec3::ZERO).expr(),
+        radius: writer.lit(5.).expr(),
+        dimension: ShapeDimension::Volume,
+    };
+
+    let init_vel = SetVelocitySphereModifier {
+        center: writer.lit(Vec3::ZERO).expr(),
+        speed: (writer.rand(ScalarType::Float) * writer.lit(10.) + writer.lit(2.)).expr(),
+    };
+
+    EffectAsset::new(
+        vec![32768],
+        Spawner::once(2000.0.into(), false),
+        writer.finish(),
+    )
+    .with_name("debris")
+    .init(init_pos)
+    .init(init_vel)
+    .init(init_age)
+    .init(init_lifetime)
+    .render(color_modifier)
+    .render(size_modifier)
+}
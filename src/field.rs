@@ -0,0 +1,197 @@
+use bevy::prelude::*;
+use crate::asteroids::AsteroidSize;
+use crate::gravity::{Orbit, GRAVITY_CONSTANT};
+
+// Asteroid field description. The same config (seed included) always produces the same field
+
+#[derive(Clone, Copy, Debug)]
+pub enum VelocityModel {
+    Converge {deviation: f32, time_to_center: f32},    // heading to a point near the origin
+    Drift {speed: f32},                                 // random direction in XZ
+    Still
+}
+
+// Scatter fills a square with asteroids moving by the VelocityModel,
+// Belt puts them on circular orbits around the field center
+
+#[derive(Clone, Copy, Debug)]
+pub enum FieldLayout {
+    Scatter,
+    Belt {ring_radius: f32, thickness: f32, orbital_speed: f32}
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct FieldConfig {
+    pub seed: u64,
+    pub layout: FieldLayout,
+    pub counts: [usize; 3],         // per asteroid type
+    pub shapes: usize,              // procedural rock variants shared by all types
+    pub density: f32,               // asteroids per 1000 x 1000 of XZ, sets the field extent
+    pub height: (f32, f32),
+    pub scale: (f32, f32),
+    pub small_bias: f32,            // 1 - uniform sizes, above 1 - mostly small ones
+    pub velocity: VelocityModel
+}
+
+impl Default for FieldConfig {
+    fn default() -> Self {
+        Self {
+            seed: seed_from_env(),
+            layout: layout_from_env(),
+            counts: [20, 20, 20],
+            shapes: 12,
+            density: 23.4,
+            height: (100., 200.),
+            scale: (0.7, 1.5),
+            small_bias: 2.,
+            velocity: VelocityModel::Converge {deviation: 100., time_to_center: 84.}
+        }
+    }
+}
+
+impl FieldConfig {
+    pub fn half_extent(&self) -> f32 {
+        let total: usize = self.counts.iter().sum();
+        (total as f32 / self.density).sqrt() * 1000. / 2.
+    }
+
+    // v = sqrt(GM / r) is a circular orbit, so the central mass is sized to give orbital_speed at the ring radius
+
+    pub fn belt_orbit(&self) -> Option<Orbit> {
+        let FieldLayout::Belt {ring_radius, orbital_speed, ..} = self.layout else {
+            return None;
+        };
+        Some(Orbit {
+            center: Vec3::new(0., (self.height.0 + self.height.1) / 2., 0.),
+            mass: orbital_speed * orbital_speed * ring_radius / GRAVITY_CONSTANT
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AsteroidSpec {
+    pub kind: usize,
+    pub shape: usize,
+    pub position: Vec3,
+    pub scale: f32,
+    pub velocity: Vec3,
+    pub spin: Vec3,
+    pub toughness: f32,    // health multiplier
+    pub size: AsteroidSize,
+    pub orbit: Option<Orbit>
+}
+
+// SPACE_SEED=<u64> replays a field, otherwise a random seed is picked
+
+fn seed_from_env() -> u64 {
+    std::env::var("SPACE_SEED").ok()
+    .and_then(|v| v.parse().ok())
+    .unwrap_or_else(|| fastrand::u64(..))
+}
+
+// SPACE_FIELD=belt switches to the belt layout
+
+fn layout_from_env() -> FieldLayout {
+    match std::env::var("SPACE_FIELD").as_deref() {
+        Ok("belt") => FieldLayout::Belt {ring_radius: 500., thickness: 120., orbital_speed: 12.},
+        _ => FieldLayout::Scatter
+    }
+}
+
+// ---
+
+pub fn generate(config: &FieldConfig) -> Vec<AsteroidSpec> {
+    let mut rng = fastrand::Rng::with_seed(config.seed);
+    let extent = config.half_extent();
+    let mut specs = Vec::new();
+
+    for (kind, count) in config.counts.iter().enumerate() {
+        for _ in 0 .. *count {
+            let orbit = config.belt_orbit();
+            let (position, velocity) = match (config.layout, orbit) {
+                (FieldLayout::Belt {ring_radius, thickness, ..}, Some(orbit)) => {
+                    let angle = range(&mut rng, 0., std::f32::consts::TAU);
+                    let r = ring_radius + range(&mut rng, -thickness, thickness) / 2.;
+                    let radial = Vec3::new(angle.cos(), 0., angle.sin());
+                    let position = orbit.center + radial * r + Vec3::Y * range(&mut rng, -thickness, thickness) / 4.;
+                    let speed = (GRAVITY_CONSTANT * orbit.mass / r).sqrt();
+                    (position, radial.cross(Vec3::Y) * speed)
+                },
+                _ => {
+                    let position = Vec3::new(
+                        range(&mut rng, -extent, extent),
+                        range(&mut rng, config.height.0, config.height.1),
+                        range(&mut rng, -extent, extent)
+                    );
+                    (position, scatter_velocity(&mut rng, config.velocity, position))
+                }
+            };
+            let scale = config.scale.0 + (config.scale.1 - config.scale.0) * rng.f32().powf(config.small_bias);
+            let spin = Vec3::new(range(&mut rng, -0.3, 0.3), range(&mut rng, -0.3, 0.3), range(&mut rng, -0.3, 0.3));
+            let shape = rng.usize(0 .. config.shapes);
+            specs.push(AsteroidSpec {kind, shape, position, scale, velocity, spin, toughness: 1., size: AsteroidSize::Large, orbit});
+        }
+    }
+    specs
+}
+
+// ---
+
+// Wave n comes in from the field edge towards the center, more, faster and tougher with every wave
+
+pub fn wave(config: &FieldConfig, n: u32) -> Vec<AsteroidSpec> {
+    let mut rng = fastrand::Rng::with_seed(config.seed ^ (n as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    let edge = config.half_extent() * 1.1;
+    let count = WAVE_BASE_COUNT + n as usize * WAVE_COUNT_GROWTH;
+    let speed = WAVE_BASE_SPEED * (1. + n as f32 * WAVE_SPEED_GROWTH);
+    let toughness = 1. + n as f32 * WAVE_TOUGHNESS_GROWTH;
+
+    (0 .. count).map(|_| {
+        let angle = range(&mut rng, 0., std::f32::consts::TAU);
+        let position = Vec3::new(angle.cos() * edge, range(&mut rng, config.height.0, config.height.1), angle.sin() * edge);
+        let target = Vec3::new(range(&mut rng, -100., 100.), 0., range(&mut rng, -100., 100.));
+        AsteroidSpec {
+            kind: rng.usize(0 .. config.counts.len()),
+            shape: rng.usize(0 .. config.shapes),
+            position,
+            scale: config.scale.0 + (config.scale.1 - config.scale.0) * rng.f32().powf(config.small_bias),
+            velocity: (target - position).normalize_or_zero() * speed,
+            spin: Vec3::new(range(&mut rng, -0.3, 0.3), range(&mut rng, -0.3, 0.3), range(&mut rng, -0.3, 0.3)),
+            toughness,
+            size: AsteroidSize::Large,
+            orbit: None
+        }
+    }).collect()
+}
+
+const WAVE_BASE_COUNT: usize = 6;
+const WAVE_COUNT_GROWTH: usize = 3;
+const WAVE_BASE_SPEED: f32 = 8.;
+const WAVE_SPEED_GROWTH: f32 = 0.15;
+const WAVE_TOUGHNESS_GROWTH: f32 = 0.25;
+
+// ---
+
+fn scatter_velocity(rng: &mut fastrand::Rng, model: VelocityModel, position: Vec3) -> Vec3 {
+    match model {
+        VelocityModel::Converge {deviation, time_to_center} => {
+            let target = Vec3::new(
+                range(rng, -deviation, deviation),
+                range(rng, -deviation, deviation),
+                range(rng, -deviation, deviation)
+            );
+            (target - position) / time_to_center
+        },
+        VelocityModel::Drift {speed} => {
+            let angle = range(rng, 0., std::f32::consts::TAU);
+            Vec3::new(angle.cos(), 0., angle.sin()) * speed
+        },
+        VelocityModel::Still => Vec3::ZERO
+    }
+}
+
+// ---
+
+pub(crate) fn range(rng: &mut fastrand::Rng, min: f32, max: f32) -> f32 {
+    min + (max - min) * rng.f32()
+}
@@ -0,0 +1,193 @@
+use bevy::prelude::*;
+use avian3d::prelude::*;
+use bevy::pbr::NotShadowCaster;
+
+use crate::layers::dock_layers;
+use crate::damage::{DamageEvent, DamageKind};
+
+pub struct GravityPlugin;
+impl Plugin for GravityPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, startup);
+        app.add_systems(Update, swallow);
+        app.add_systems(PhysicsSchedule, (attract, orbit).chain().in_set(PhysicsStepSet::First));
+    }
+}
+
+// ---
+
+// Inverse square pull on every dynamic body inside the radius
+
+#[derive(Component, Clone, Copy, Debug)]
+pub struct GravityWell {
+    pub mass: f32,
+    pub radius: f32
+}
+
+// Pull towards a point that only the body itself feels, keeps belt asteroids on their orbits
+// without dragging everything else into the field center
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+pub struct Orbit {
+    pub center: Vec3,
+    pub mass: f32
+}
+
+// Whatever crosses the horizon is crushed
+
+#[derive(Component)]
+pub struct BlackHole {
+    horizon: f32
+}
+
+pub const GRAVITY_CONSTANT: f32 = 1.;
+const GRAVITY_MIN_DISTANCE: f32 = 10.;     // keeps the pull finite near the center
+const BLACK_HOLE_DAMAGE: f32 = 200.;        // per second inside the horizon
+
+const PLANETOID_POSITION: Vec3 = Vec3::new(650., 150., -450.);
+const PLANETOID_RADIUS: f32 = 60.;
+const PLANETOID_WELL: GravityWell = GravityWell {mass: 18000., radius: 500.};
+
+const BLACK_HOLE_POSITION: Vec3 = Vec3::new(-700., 150., 550.);
+const BLACK_HOLE_HORIZON: f32 = 15.;
+const BLACK_HOLE_WELL: GravityWell = GravityWell {mass: 60000., radius: 600.};
+
+// ---
+
+fn startup(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    commands.spawn((
+        PbrBundle {
+            material: materials.add(Color::srgb(0.35, 0.3, 0.25)),
+            mesh: meshes.add(Sphere::new(PLANETOID_RADIUS).mesh().ico(5).expect("too many icosphere subdivisions")),
+            transform: Transform::from_translation(PLANETOID_POSITION),
+            ..default()
+        },
+        Name::new("Planetoid"),
+        Collider::sphere(PLANETOID_RADIUS),
+        dock_layers(),
+        RigidBody::Static,
+        PLANETOID_WELL
+    ));
+
+    commands.spawn((
+        PbrBundle {
+            material: materials.add(StandardMaterial {
+                base_color: Color::BLACK,
+                unlit: true,
+                ..default()
+            }),
+            mesh: meshes.add(Sphere::new(BLACK_HOLE_HORIZON)),
+            transform: Transform::from_translation(BLACK_HOLE_POSITION),
+            ..default()
+        },
+        Name::new("BlackHole"),
+        BlackHole {horizon: BLACK_HOLE_HORIZON},
+        BLACK_HOLE_WELL,
+        NotShadowCaster
+    ))
+    .with_children(|p| {
+        p.spawn((
+            PbrBundle {
+                material: materials.add(StandardMaterial {
+                    base_color: Color::srgb(1., 0.5, 0.1),
+                    emissive: LinearRgba::rgb(8., 3., 0.5),
+                    ..default()
+                }),
+                mesh: meshes.add(Torus::new(BLACK_HOLE_HORIZON * 1.3, BLACK_HOLE_HORIZON * 2.5)),
+                transform: Transform::from_rotation(Quat::from_rotation_x(0.3)),
+                ..default()
+            },
+            NotShadowCaster
+        ));
+    });
+}
+
+// ---
+
+// Bodies on an Orbit feel only their own center, the belt passes through the wells and would be torn apart
+
+fn attract(
+    mut commands: Commands,
+    wells_q: Query<(Entity, &GlobalTransform, &GravityWell)>,
+    mut bodies_q: Query<(Entity, &Transform, &RigidBody, &Mass, Option<&mut ExternalForce>), Without<Orbit>>,
+) {
+    for (e, tr, body, mass, of) in bodies_q.iter_mut() {
+        if *body != RigidBody::Dynamic {
+            continue;
+        }
+        let mut force = Vec3::ZERO;
+        for (we, wt, well) in wells_q.iter() {
+            if we == e {
+                continue;
+            }
+            let span = wt.translation() - tr.translation;
+            let distance = span.length();
+            if distance > well.radius || distance < f32::EPSILON {
+                continue;
+            }
+            let d = f32::max(distance, GRAVITY_MIN_DISTANCE);
+            force += span / distance * GRAVITY_CONSTANT * well.mass * mass.0 / (d * d);
+        }
+        if force != Vec3::ZERO {
+            add_force(&mut commands, e, of, force);
+        }
+    }
+}
+
+// ---
+
+fn orbit(
+    mut commands: Commands,
+    mut bodies_q: Query<(Entity, &Transform, &Orbit, &Mass, Option<&mut ExternalForce>)>,
+) {
+    for (e, tr, orbit, mass, of) in bodies_q.iter_mut() {
+        let span = orbit.center - tr.translation;
+        let distance = f32::max(span.length(), GRAVITY_MIN_DISTANCE);
+        add_force(&mut commands, e, of, span.normalize_or_zero() * GRAVITY_CONSTANT * orbit.mass * mass.0 / (distance * distance));
+    }
+}
+
+// ---
+
+fn swallow(
+    holes_q: Query<(Entity, &GlobalTransform, &BlackHole)>,
+    victims_q: Query<(Entity, &Transform), With<RigidBody>>,
+    mut damage_writer: EventWriter<DamageEvent>,
+    time: Res<Time>
+) {
+    for (he, ht, hole) in holes_q.iter() {
+        for (ve, vt) in victims_q.iter() {
+            if vt.translation.distance(ht.translation()) < hole.horizon {
+                damage_writer.send(DamageEvent {
+                    source: he,
+                    target: ve,
+                    amount: BLACK_HOLE_DAMAGE * time.delta_seconds(),
+                    kind: DamageKind::Gravity,
+                    point: vt.translation
+                });
+            }
+        }
+    }
+}
+
+// ---
+
+// Rigid bodies come with a persistent ExternalForce, forces added every step must not pile up.
+// Everything pushing bodies around continuously goes through here from the start of the physics step,
+// so the pushes add up within one step and are cleared by it, whatever the frame rate
+
+pub fn add_force(commands: &mut Commands, e: Entity, force: Option<Mut<ExternalForce>>, v: Vec3) {
+    match force {
+        Some(mut ef) => {
+            let total = ef.force() + v;
+            *ef = ExternalForce::new(total).with_persistence(false);
+        },
+        None => {
+            commands.entity(e).insert(ExternalForce::new(v).with_persistence(false));
+        }
+    }
+}
@@ -8,31 +8,100 @@ impl Plugin for LaserPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, spawn);
         app.add_systems(Update, setup.run_if(in_state(GameState::Setup)));
-        app.add_systems(Update, input);
-        app.add_systems(Update, shot.run_if(on_event::<LaserShot>()));
-        app.add_event::<LaserShot>();
+        app.add_systems(Update, shot.run_if(on_event::<WeaponShot>()));
+        app.add_systems(Update, (cool, update_indicators).chain().run_if(in_state(GameState::Game)));
     }
 }
 
-use crate::{drone::{Manual, Fluel}, effects::{laser, small_blast}, GameState, Health};
-
-// ---
-
-#[derive(Event)]
-pub struct LaserShot(Entity);
+use crate::{effects::laser, GameState};
+use crate::damage::{DamageEvent, DamageKind};
+use crate::effect_pool::{EffectKind, PlayEffect};
+use crate::weapon::{shooter_transform, Projectile, WeaponShot};
+use crate::beam::{fire, spawn_beam, Beam, BeamAssets};
+use crate::layers::weapon_filter;
 
 use crate::NotReady;
-use crate::drone::Drone;
+use crate::drone::{Drone, Manual};
+use crate::ui::{RegisterWidgets, ULayout, UpdateWidgets, WType, WidgetRegData, WidgetUpdateData};
+use bevy::color::palettes::css::{ORANGE_RED, YELLOW_GREEN};
 
 #[derive(Component)]
 pub struct LaserTempMarker;
 
 #[derive(Component)]
 
-pub struct LaserEffects([Entity; 4]);
+pub struct LaserEffects([Entity; 2]);
+
+// Two emitters, spread apart sideways from the shooter's center
+
+#[derive(Component)]
+pub struct LaserBeams {
+    beams: [Entity; 2],
+    spread: f32
+}
+
+impl LaserBeams {
+    pub fn new(beams: [Entity; 2], spread: f32) -> Self {
+        Self {beams, spread}
+    }
+}
+
+const LASER_BEAM_WIDTH: f32 = 0.15;
+const LASER_DRONE_SPREAD: f32 = 5.2;
+
+// Heat per emitter, an overheated emitter is silent until it cools down to LASER_HEAT_RESUME
+
+#[derive(Component, Default)]
+pub struct LaserHeat {
+    heat: [f32; 2],
+    overheated: [bool; 2]
+}
+
+impl LaserHeat {
+    fn warm(&mut self, i: usize, v: f32) {
+        self.heat[i] = f32::min(self.heat[i] + v, LASER_HEAT_MAX);
+        if self.heat[i] >= LASER_HEAT_MAX {
+            self.overheated[i] = true;
+        }
+    }
+
+    fn cool(&mut self, v: f32) {
+        for i in 0..2 {
+            self.heat[i] = f32::max(self.heat[i] - v, 0.);
+            if self.heat[i] <= LASER_HEAT_RESUME {
+                self.overheated[i] = false;
+            }
+        }
+    }
+
+    // full damage when cold, down to (1 - LASER_HOT_PENALTY) at the limit
+
+    fn damage_factor(&self, i: usize) -> f32 {
+        1. - LASER_HOT_PENALTY * self.heat[i] / LASER_HEAT_MAX
+    }
+
+    fn percent(&self) -> f32 {
+        f32::max(self.heat[0], self.heat[1]) / LASER_HEAT_MAX
+    }
 
-const LASER_DPS: f32 = 0.05; 
-const LASER_SHOT_COST: f32 = 0.1;
+    // part of the emitters able to fire, the shot is paid for those only
+
+    pub fn active_share(&self) -> f32 {
+        self.overheated.iter().filter(|o| !**o).count() as f32 / 2.
+    }
+
+    fn is_overheated(&self) -> bool {
+        self.overheated[0] || self.overheated[1]
+    }
+}
+
+const LASER_HEAT_MAX: f32 = 100.;
+const LASER_HEAT_RATE: f32 = 20.;       // per second of fire
+const LASER_COOL_RATE: f32 = 12.;       // per second
+const LASER_HEAT_RESUME: f32 = 30.;
+const LASER_HOT_PENALTY: f32 = 0.6;
+
+const I_HEAT: (&str, &str) = ("heat", "H");
 
 // ---
 
@@ -49,6 +118,9 @@ fn setup (
         check_q: Query<Entity, (With<NotReady>, With<LaserTempMarker>)>,
         drones_q: Query<Entity, (With<Drone>, Without<LaserEffects>)>,
         mut effects: ResMut<Assets<EffectAsset>>,
+        mut materials: ResMut<Assets<StandardMaterial>>,
+        beam_assets: Res<BeamAssets>,
+        mut writer: EventWriter<RegisterWidgets>,
     ) {
         if drones_q.is_empty() {
             if let Ok(e) = check_q.get_single() {
@@ -58,17 +130,16 @@ fn setup (
         }
     
         let muzzle_handle = effects.add(laser());
-        let blast_handle = effects.add(small_blast());
     
         for drone_entity in drones_q.iter() {
-            let mut effect_ents = [Entity::PLACEHOLDER;4];
+            let mut effect_ents = [Entity::PLACEHOLDER;2];
 
             for i in 0..2 {
                 let sign = if i == 0  {-1.} else {1.};
                 let muzzle = commands.spawn(
                     ParticleEffectBundle {
                         effect: ParticleEffect::new(muzzle_handle.clone()),
-                        transform: Transform::from_xyz(sign * 5.2, 0.,0.,).with_rotation(Quat::from_rotation_x(f32::to_radians(-90.))),
+                        transform: Transform::from_xyz(sign * LASER_DRONE_SPREAD, 0.,0.,).with_rotation(Quat::from_rotation_x(f32::to_radians(-90.))),
                         ..default()
                     },
                 ).id();
@@ -76,101 +147,117 @@ fn setup (
                 commands.entity(drone_entity).add_child(muzzle);
                 effect_ents[i] = muzzle;
             }
-    
-            for i in 2..4 {
-                let blast = commands.spawn((
-                    ParticleEffectBundle::new(blast_handle.clone()),
-                )).id();
-
-                effect_ents[i] = blast;
-            }
-            commands.entity(drone_entity).insert(LaserEffects(effect_ents));
+            let beams = [0, 1].map(|_| spawn_beam(&mut commands, &beam_assets, &mut materials, LinearRgba::rgb(0.2, 1., 0.2)));
+            commands.entity(drone_entity).insert((LaserEffects(effect_ents), LaserBeams::new(beams, LASER_DRONE_SPREAD), LaserHeat::default()));
         }
-    
-    }
 
-// ---
-
-fn input (
-    keys: Res<ButtonInput<KeyCode>>,
-    drone_q: Query<Entity , With<Manual>>,
-    mut ev_writer: EventWriter<LaserShot>
-) {
-    if keys.pressed(KeyCode::ControlRight) {
-        if let Ok(de) = drone_q.get_single() {
-            ev_writer.send(LaserShot(de));
-        }
+        writer.send(
+            RegisterWidgets(
+                vec![
+                    WidgetRegData {
+                        key: I_HEAT.0,
+                        label: I_HEAT.1,
+                        parent: ULayout::SidebarLeft,
+                        wtype: WType::Float,
+                        image: None,
+                        start: 1,
+                        span: 2,
+                        default: Some(0.)
+                    },
+                ]
+            )
+        );
+    
     }
-}
 
 // ---
 
 fn shot(
-    mut ev_reader: EventReader<LaserShot>,
-    // mut ev_writer: EventWriter<CollisionEvent>,
-    mut ev_writer: EventWriter<CollisionEnded>,
-    // rapier_context: Res<RapierContext>,
+    mut ev_reader: EventReader<WeaponShot>,
+    mut ev_writer: EventWriter<DamageEvent>,
+    mut fx_writer: EventWriter<PlayEffect>,
     spatial: SpatialQuery,
-    mut drone_q: Query<(&Transform, &LaserEffects, &mut Fluel)>,
-    mut effects_q: Query<(&mut Transform, &mut EffectSpawner), Without<LaserEffects>>,
-    mut victim_q: Query<Option<&mut Health>> ,
+    mut shooter_q: Query<(&Transform, Option<&Parent>, Option<&LaserEffects>, &LaserBeams, &mut LaserHeat)>,
+    globals_q: Query<&GlobalTransform>,
+    mut effects_q: Query<&mut EffectSpawner>,
+    mut beam_q: Query<(&mut Beam, &mut Transform, &mut Visibility), Without<LaserBeams>>,
 ) {
-    for ev in ev_reader.read() {
-        let Ok((drone_transform, effect_entities, mut fluel)) = drone_q.get_mut(ev.0) else {
+    for WeaponShot(se, weapon, duration) in ev_reader.read() {
+        if weapon.projectile != Projectile::Beam {
+            continue;
+        }
+        // drones and turrets alike, only drones have muzzle flashes
+
+        let Ok((transform, parent, effect_entities, beams, mut heat)) = shooter_q.get_mut(*se) else {
             continue;
         };
-        if let Ok(mut effects) = effects_q.get_many_mut(effect_entities.0) {
-    
-            let mut i =  0;
-            for ef in &mut effects {
-                if i < 2 {
-                    ef.1.reset();
-                    fluel.loss(LASER_SHOT_COST);
-                } else {
-                    let shift = (if i == 2 {-1.} else {1.}) * 5.2;
-                    let ray_origin = drone_transform.translation + drone_transform.right() * shift  + drone_transform.forward() * 5.; 
-
-                    // if let Some((e, toi)) = rapier_context.cast_ray(
-                    //     ray_origin, 
-                    //     drone_transform.forward().into(),
-                    //     200.,
-                    //     true, 
-                    //     QueryFilter::default()
-                    // ) {
-                    //     ef.0.translation = ray_origin + drone_transform.forward() * toi;
-                    //     ef.1.reset();
-                    //     if let Ok(oh) = victim_q.get_mut(e) {
-                    //         if let Some(mut h) = oh {
-                    //             ev_writer.send(CollisionEvent::Started(Entity::PLACEHOLDER, e, CollisionEventFlags::all()));
-                    //             h.0 -= LASER_DPS;
-                    //         }
-                    //     }
-                    // }
-
-                    if let Some(hit) = spatial.cast_ray(
-                        ray_origin, 
-                        drone_transform.forward().into(),
-                        200.,
-                        true, 
-                        SpatialQueryFilter::default()
-                    ) {
-                        
-                        ef.0.translation = ray_origin + drone_transform.forward() * hit.time_of_impact;
-                        ef.1.reset();
-                        if let Ok(oh) = victim_q.get_mut(hit.entity) {
-                            if let Some(mut h) = oh {
-                                ev_writer.send(CollisionEnded(Entity::PLACEHOLDER, hit.entity));
-                                h.0 -= LASER_DPS;
-                            }
-                        }
-                    }
+        let drone_transform = shooter_transform(transform, parent, &globals_q);
+
+        for i in 0 .. 2 {
+            if heat.overheated[i] {
+                continue;
+            }
+            let damage = weapon.damage * duration * heat.damage_factor(i);
+            heat.warm(i, LASER_HEAT_RATE * duration);
 
+            if let Some(muzzles) = effect_entities {
+                if let Ok(mut spawner) = effects_q.get_mut(muzzles.0[i]) {
+                    spawner.reset();
+                }
+            }
 
+            let shift = (if i == 0 {-1.} else {1.}) * beams.spread;
+            let ray_origin = drone_transform.translation + drone_transform.right() * shift  + drone_transform.forward() * 5.; 
 
+            let mut ray_end = ray_origin + drone_transform.forward() * weapon.range;
+            if let Some(hit) = spatial.cast_ray(
+                ray_origin, 
+                drone_transform.forward().into(),
+                weapon.range,
+                true, 
+                weapon_filter(*se)
+            ) {
+                ray_end = ray_origin + drone_transform.forward() * hit.time_of_impact;
+                fx_writer.send(PlayEffect(EffectKind::Impact, ray_end));
+                ev_writer.send(DamageEvent {
+                    source: *se,
+                    target: hit.entity,
+                    amount: damage,
+                    kind: DamageKind::Laser,
+                    point: ray_end
+                });
+            }
 
-                }
-                i += 1; 
+            if let Ok((mut beam, mut bt, mut bv)) = beam_q.get_mut(beams.beams[i]) {
+                fire(&mut beam, &mut bt, &mut bv, ray_origin, ray_end, LASER_BEAM_WIDTH);
             }
         }
     }
 }
+
+// ---
+
+fn cool(
+    mut heat_q: Query<&mut LaserHeat>,
+    time: Res<Time>
+) {
+    // cold emitters are left untouched, so Changed<LaserHeat> means a real change
+    for mut heat in heat_q.iter_mut() {
+        if heat.bypass_change_detection().heat.iter().any(|h| *h > 0.) {
+            heat.cool(LASER_COOL_RATE * time.delta_seconds());
+        }
+    }
+}
+
+// ---
+
+fn update_indicators(
+    drone_q: Query<&LaserHeat, (With<Manual>, Changed<LaserHeat>)>,
+    mut writer: EventWriter<UpdateWidgets>
+) {
+    if let Ok(heat) = drone_q.get_single() {
+        writer.send(UpdateWidgets(vec![
+            WidgetUpdateData::from_key_value_color(I_HEAT.0, heat.percent() * 100., if heat.is_overheated() {ORANGE_RED.into()} else {YELLOW_GREEN.into()})
+        ]));
+    }
+}
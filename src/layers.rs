@@ -0,0 +1,49 @@
+use bevy::prelude::*;
+use avian3d::prelude::*;
+
+// Every collider gets its layers at spawn time from here, and every spatial query filters by them
+
+#[derive(PhysicsLayer, Clone, Copy, Debug)]
+pub enum GameLayer {
+    Ship,
+    Asteroid,
+    Projectile,
+    Dock,
+    Sensor
+}
+
+pub fn ship_layers() -> CollisionLayers {
+    CollisionLayers::new(GameLayer::Ship, [GameLayer::Asteroid, GameLayer::Projectile, GameLayer::Dock, GameLayer::Sensor])
+}
+
+pub fn asteroid_layers() -> CollisionLayers {
+    CollisionLayers::new(GameLayer::Asteroid, [GameLayer::Ship, GameLayer::Asteroid, GameLayer::Projectile, GameLayer::Dock])
+}
+
+pub fn projectile_layers() -> CollisionLayers {
+    CollisionLayers::new(GameLayer::Projectile, [GameLayer::Ship, GameLayer::Asteroid, GameLayer::Dock])
+}
+
+pub fn dock_layers() -> CollisionLayers {
+    CollisionLayers::new(GameLayer::Dock, [GameLayer::Ship, GameLayer::Asteroid, GameLayer::Projectile])
+}
+
+// Pickups, only ships pass through them
+
+pub fn sensor_layers() -> CollisionLayers {
+    CollisionLayers::new(GameLayer::Sensor, [GameLayer::Ship])
+}
+
+// ---
+
+// Rays and blasts of a weapon, never touching the one who fired
+
+pub fn weapon_filter(owner: Entity) -> SpatialQueryFilter {
+    SpatialQueryFilter::from_mask([GameLayer::Ship, GameLayer::Asteroid, GameLayer::Dock]).with_excluded_entities([owner])
+}
+
+// Things that can be clicked as a target, pickups included so the tractor beam can lock on them
+
+pub fn pick_filter() -> SpatialQueryFilter {
+    SpatialQueryFilter::from_mask([GameLayer::Asteroid, GameLayer::Dock, GameLayer::Sensor])
+}
@@ -12,6 +12,7 @@ mod env;
 mod drone;
 mod ui;
 mod effects;
+mod effect_pool;
 mod asteroids;
 mod docks;
 mod missile;
@@ -19,12 +20,81 @@ mod laser;
 mod crosshair;
 mod target_select;
 mod autopilot;
+mod weapon;
+mod cannon;
+mod beam;
+mod damage;
+mod score;
+mod collision_routing;
+mod layers;
+mod field;
+mod rocks;
+mod mining;
+mod pickups;
+mod tractor;
+mod gravity;
+mod base;
+mod construction;
+mod turret;
+mod waves;
 // ===============
 
 #[derive(Component)]
 pub struct Target;
 #[derive(Component)]
-pub struct Health(pub f32);
+pub struct Health {
+    current: f32,
+    max: f32,
+    regen: Option<f32>     // per second
+}
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Self {current: max, max, regen: None}
+    }
+
+    pub fn with_regen(mut self, v: f32) -> Self {
+        self.regen = Some(v);
+        self
+    }
+
+    // true only for the hit that takes it to zero
+
+    pub fn damage(&mut self, v: f32) -> bool {
+        if self.is_dead() {
+            return false;
+        }
+        self.current = (self.current - v).clamp(0., self.max);
+        self.is_dead()
+    }
+
+    pub fn heal(&mut self, v: f32) {
+        if self.is_dead() {
+            return;
+        }
+        self.current = (self.current + v).clamp(0., self.max);
+    }
+
+    pub fn regen(&self) -> Option<f32> {
+        self.regen
+    }
+
+    pub fn max(&self) -> f32 {
+        self.max
+    }
+
+    pub fn percent(&self) -> f32 {
+        self.current / self.max
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0.
+    }
+}
+
+// Plain despawn on death, for entities without their own destruction logic
+
+#[derive(Component)]
+pub struct DespawnOnDeath;
 
 #[derive(Component)]
 pub struct LifeTime(pub f32);
@@ -64,6 +134,7 @@ fn main() {
         crosshair::CrosshairPlugin,
         target_select::TargetSelectPlugin,
         autopilot::AutoPilotPlugin,
+        effect_pool::EffectPoolPlugin,
 
 
         // WorldInspectorPlugin::new(),
@@ -73,6 +144,22 @@ fn main() {
         ui::UIPlugin,
         HanabiPlugin
     ))
+    .add_plugins((
+        weapon::WeaponPlugin,
+        cannon::CannonPlugin,
+        beam::BeamPlugin,
+        damage::DamagePlugin,
+        score::ScorePlugin,
+        collision_routing::CollisionRoutingPlugin,
+        waves::WavesPlugin,
+        mining::MiningPlugin,
+        pickups::PickupsPlugin,
+        tractor::TractorPlugin,
+        gravity::GravityPlugin,
+        base::BasePlugin,
+        construction::ConstructionPlugin,
+        turret::TurretPlugin,
+    ))
     .init_state::<GameState>()
     .add_systems(Update, check_ready.run_if(in_state(GameState::Setup)))
     .add_systems(Update, (cleanup, overtime).run_if(on_timer(Duration::from_secs(1))))
@@ -83,9 +170,15 @@ fn main() {
 pub enum GameState{
     #[default]
     Setup,
-    Game
+    Game,
+    Over
 }
 
+// Why the game ended, shown once the state is Over
+
+#[derive(Resource)]
+pub struct GameOver(pub &'static str);
+
 // ---
 
 fn check_ready(
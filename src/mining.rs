@@ -0,0 +1,195 @@
+use bevy::prelude::*;
+use avian3d::prelude::*;
+use bevy::color::palettes::css::{ORANGE_RED, YELLOW_GREEN};
+use crate::{GameState, NotReady};
+use crate::drone::{Drone, Manual};
+use crate::ui::{RegisterWidgets, ULayout, UpdateWidgets, WType, WidgetRegData, WidgetUpdateData};
+
+pub struct MiningPlugin;
+impl Plugin for MiningPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Yield>();
+        app.add_systems(Startup, spawn);
+        app.add_systems(Update, setup.run_if(in_state(GameState::Setup)));
+        app.add_systems(Update, (ballast, update_indicators).run_if(in_state(GameState::Game)));
+    }
+}
+
+// ---
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Ore {
+    Ice,
+    Metal,
+    Rare
+}
+
+pub const ORE_KINDS: usize = 3;
+
+// Share of each ore in the asteroid body, indexed by Ore
+
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Composition(pub [f32; ORE_KINDS]);
+
+// per asteroid type
+
+pub const COMPOSITIONS: [Composition; 3] = [
+    Composition([0.8, 0.2, 0.]),        // icy
+    Composition([0.1, 0.85, 0.05]),     // metallic
+    Composition([0.2, 0.5, 0.3])        // ore bearing
+];
+
+// Ore carried by a ship, the hold is shared by all ores and its load adds to the ship mass
+
+#[derive(Component)]
+pub struct Cargo {
+    amounts: [f32; ORE_KINDS],
+    capacity: f32
+}
+
+impl Cargo {
+    pub fn new(capacity: f32) -> Self {
+        Self {amounts: [0.; ORE_KINDS], capacity}
+    }
+
+    // how much of v fits in
+
+    pub fn add(&mut self, ore: Ore, v: f32) -> f32 {
+        let accepted = f32::min(v, self.free());
+        self.amounts[ore as usize] += accepted;
+        accepted
+    }
+
+    pub fn get(&self, ore: Ore) -> f32 {
+        self.amounts[ore as usize]
+    }
+
+    pub fn unload(&mut self) -> [f32; ORE_KINDS] {
+        std::mem::take(&mut self.amounts)
+    }
+
+    pub fn load(&self) -> f32 {
+        self.amounts.iter().sum()
+    }
+
+    pub fn free(&self) -> f32 {
+        self.capacity - self.load()
+    }
+
+    pub fn mass(&self) -> f32 {
+        self.load() * CARGO_UNIT_MASS
+    }
+
+    pub fn percent(&self) -> f32 {
+        self.load() / self.capacity
+    }
+}
+
+const CARGO_CAPACITY: f32 = 100.;
+const CARGO_UNIT_MASS: f32 = 0.5;
+
+// Ship mass without cargo, taken once the physics has computed it
+
+#[derive(Component)]
+pub struct HullMass(f32);
+
+// Ore released by an asteroid piece that is gone for good, drops as pickups where the piece was
+
+#[derive(Event)]
+pub struct Yield {
+    pub amounts: [f32; ORE_KINDS],
+    pub point: Vec3,
+    pub velocity: Vec3
+}
+
+// Yield of a scale 1 asteroid, goes with scale cubed like its mass
+
+pub const ORE_YIELD: f32 = 40.;
+
+#[derive(Component)]
+pub struct MiningTempMarker;
+
+const I_ICE: (&str, &str) = ("ice", "I");
+const I_METAL: (&str, &str) = ("metal", "M");
+const I_RARE: (&str, &str) = ("rare", "R");
+const I_CARGO: (&str, &str) = ("cargo", "C");
+
+// ---
+
+fn spawn(
+    mut commands: Commands,
+) {
+    commands.spawn((NotReady, MiningTempMarker));
+}
+
+// ---
+
+fn setup (
+    mut commands: Commands,
+    drones_q: Query<Entity, (With<Drone>, Without<Cargo>)>,
+    check_q: Query<Entity, (With<NotReady>, With<MiningTempMarker>)>,
+    mut writer: EventWriter<RegisterWidgets>,
+) {
+    if drones_q.is_empty() {
+        if let Ok(e) = check_q.get_single() {
+            commands.entity(e).despawn();
+        }
+        return;
+    }
+    for de in drones_q.iter() {
+        commands.entity(de).insert(Cargo::new(CARGO_CAPACITY));
+    }
+    writer.send(
+        RegisterWidgets(
+            [I_CARGO, I_ICE, I_METAL, I_RARE].into_iter().map(|(key, label)| WidgetRegData {
+                key,
+                label,
+                parent: ULayout::SidebarRight,
+                wtype: WType::Float,
+                image: None,
+                start: 1,
+                span: 2,
+                default: Some(0.)
+            }).collect()
+        )
+    );
+}
+
+// ---
+
+fn ballast(
+    mut commands: Commands,
+    mut drone_q: Query<(Entity, &Cargo, &mut Mass, &mut InverseMass, Option<&HullMass>), Changed<Cargo>>,
+) {
+    for (e, cargo, mut mass, mut inverse, hull) in drone_q.iter_mut() {
+        let hull = match hull {
+            Some(h) => h.0,
+            None => {
+                if mass.0 <= 0. {
+                    continue;
+                }
+                commands.entity(e).insert(HullMass(mass.0));
+                mass.0
+            }
+        };
+        mass.0 = hull + cargo.mass();
+        inverse.0 = 1. / mass.0;
+    }
+}
+
+// ---
+
+fn update_indicators(
+    drone_q: Query<&Cargo, (With<Manual>, Changed<Cargo>)>,
+    mut writer: EventWriter<UpdateWidgets>
+) {
+    if let Ok(cargo) = drone_q.get_single() {
+        let color = if cargo.free() <= 0. {ORANGE_RED.into()} else {YELLOW_GREEN.into()};
+        writer.send(UpdateWidgets(vec![
+            WidgetUpdateData::from_key_value_color(I_CARGO.0, cargo.percent() * 100., color),
+            WidgetUpdateData::from_key_value(I_ICE.0, cargo.get(Ore::Ice)),
+            WidgetUpdateData::from_key_value(I_METAL.0, cargo.get(Ore::Metal)),
+            WidgetUpdateData::from_key_value(I_RARE.0, cargo.get(Ore::Rare)),
+        ]));
+    }
+}
@@ -1,7 +1,12 @@
 use bevy::prelude::*;
 use avian3d::prelude::*;
 use bevy_hanabi::prelude::*;
-use crate::{drone, GameState, NotReady};
+use crate::{drone, GameState, NotReady, Target};
+use crate::damage::{DamageEvent, DamageKind};
+use crate::collision_routing::MissileHit;
+use crate::layers::{projectile_layers, weapon_filter};
+use crate::asteroids::Asteroid;
+use crate::weapon::{shooter_transform, Aim, Projectile, WeaponShot};
 use drone::Manual;
 
 pub struct MissilePlugin;
@@ -9,12 +14,12 @@ impl Plugin for MissilePlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, startup);
         app.add_systems(Update, setup.run_if(in_state(GameState::Setup)));
-        app.add_systems(Update, (input, ));
-        app.add_systems(Update, shot.run_if(on_event::<MissileShot>()));
+        app.add_systems(Update, (guide, update_indicators));
+        app.add_systems(Update, shot.run_if(on_event::<WeaponShot>()));
         app.add_systems(Update, destroy.run_if(on_event::<MissileDestroy>()));
-        app.add_systems(Update, collision.run_if(on_event::<CollisionEnded>()));
+        app.add_systems(Update, collision.run_if(on_event::<MissileHit>()));
+        app.add_systems(Update, rearm.run_if(on_event::<DroneEvent>()));
 
-        app.add_event::<MissileShot>();
         app.add_event::<MissileDestroy>();
     }
 }
@@ -25,19 +30,38 @@ impl Plugin for MissilePlugin {
 pub struct Missile;
 
 #[derive(Component)]
-pub struct Blast;
+pub struct Warhead {
+    pub owner: Entity,
+    pub damage: f32,
+    pub radius: f32
+}
 
-#[derive(Event)]
-pub struct MissileShot;
+// Proportional navigation towards the locked target, until the motor burns out
+
+#[derive(Component)]
+pub struct Guidance {
+    pub target: Entity,
+    pub fuel: f32,
+    pub turn_rate: f32,
+    pub nav_const: f32
+}
 
 #[derive(Event)]
 pub struct MissileDestroy(Entity);
 
 use crate::LifeTime;
-use crate::effects::{blast, trail};
-use crate::drone::Drone;
+use crate::effects::trail;
+use crate::effect_pool::{EffectKind, PlayEffect};
+use crate::drone::{Drone, DroneEvent};
 use crate::ui::{RegisterWidgets, ULayout, UpdateWidgets, WType, WidgetRegData, WidgetUpdateData};
 
+#[derive(Resource)]
+pub struct MissileAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+    trail: Handle<EffectAsset>
+}
+
 
 #[derive(Component)]
 pub struct MissileTempMarker;
@@ -45,18 +69,59 @@ pub struct MissileTempMarker;
 const MISSILES_CAPACITY: i32 = 10;
 const I_MISSILES: (&str, &str) = ("m", "M");
 #[derive(Component)]
-pub struct Missiles(i32);
+pub struct Missiles{
+    count: i32,
+    reload: f32
+}
+impl Missiles {
+    pub fn new(count: i32) -> Self {
+        Self {count, reload: 0.}
+    }
+
+    pub fn gain(&mut self, v: f32) -> bool {
+        if self.is_full() {
+            return true;
+        }
+        self.reload += v;
+        let whole = self.reload.floor();
+        self.reload -= whole;
+        self.count = i32::min(self.count + whole as i32, MISSILES_CAPACITY);
+        if self.is_full() {
+            self.reload = 0.;
+        }
+        self.is_full()
+    }
+
+    pub fn take(&mut self, n: i32) -> bool {
+        if self.count < n {
+            return false;
+        }
+        self.count -= n;
+        true
+    }
+
+    pub fn get(&self) -> i32 {
+        self.count
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.count >= MISSILES_CAPACITY
+    }
+}
 
 // ---
 
 fn startup(
     mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
     mut effects: ResMut<Assets<EffectAsset>>,
 ) {
-    commands.spawn((
-        ParticleEffectBundle::new(effects.add(blast())),
-        Blast
-    ));
+    commands.insert_resource(MissileAssets {
+        mesh: meshes.add(Sphere::new(BALL_RADIUS)),
+        material: materials.add(Color::srgba(0.3, 0.3, 0.3, 0.5)),
+        trail: effects.add(trail())
+    });
     commands.spawn((NotReady, MissileTempMarker));
 }
 
@@ -75,7 +140,7 @@ fn setup (
         return;
     }    
     for de in drones_q.iter() {
-        commands.entity(de).insert(Missiles(MISSILES_CAPACITY));
+        commands.entity(de).insert(Missiles::new(MISSILES_CAPACITY));
     }
     writer.send(
         RegisterWidgets(
@@ -98,79 +163,204 @@ fn setup (
 
 // ---
 
-fn input(
-    keys: Res<ButtonInput<KeyCode>>,
-    mut ev_writer: EventWriter<MissileShot>
+const BALL_RADIUS: f32 = 0.3;
+const GUIDED_FUEL: f32 = 4.;
+const GUIDED_TURN_RATE: f32 = 2.;
+const GUIDED_NAV_CONST: f32 = 4.;
+const GUIDED_SPEED: f32 = 60.;
+const GUIDED_THRUST: f32 = 30.;
+const MISSILE_BLAST_RADIUS: f32 = 30.;
+const CLUSTER_COUNT: usize = 5;
+const CLUSTER_SPREAD: f32 = 0.08;
+const CLUSTER_BLAST_RADIUS: f32 = 20.;
+
+fn shot(
+    mut commands: Commands,
+    mut ev_reader: EventReader<WeaponShot>,
+    shooter_q: Query<(&Transform, Option<&Parent>, Option<&Aim>)>,
+    globals_q: Query<&GlobalTransform>,
+    target_q: Query<Entity, (With<Target>, With<Asteroid>)>,
+    assets: Res<MissileAssets>,
+    time: Res<Time>,
 ) {
-    if keys.just_pressed(KeyCode::ControlLeft) {
-        ev_writer.send(MissileShot);
+    for WeaponShot(se, weapon, _) in ev_reader.read() {
+        let Ok((transform, parent, aim)) = shooter_q.get(*se) else {
+            continue;
+        };
+        let shooter_trans = shooter_transform(transform, parent, &globals_q);
+        let forward: Vec3 = shooter_trans.forward().into();
+        match weapon.projectile {
+            Projectile::Missile => {
+                let mut missile = spawn_missile(&mut commands, &assets, shooter_trans.translation, forward, Warhead {owner: *se, damage: weapon.damage, radius: MISSILE_BLAST_RADIUS}, time.elapsed_seconds());
+
+                // shooters with an aim of their own guide to it, the rest to the locked target.
+                // no target locked - dumb-fire as before
+
+                if let Some(target) = aim.map(|a| a.0).or(target_q.get_single().ok()) {
+                    missile.insert(Guidance {
+                        target,
+                        fuel: GUIDED_FUEL,
+                        turn_rate: GUIDED_TURN_RATE,
+                        nav_const: GUIDED_NAV_CONST
+                    });
+                }
+            },
+            Projectile::Cluster => {
+                let half = (CLUSTER_COUNT - 1) as f32 / 2.;
+                for i in 0 .. CLUSTER_COUNT {
+                    let direction = Quat::from_axis_angle(shooter_trans.up().into(), (i as f32 - half) * CLUSTER_SPREAD) * forward;
+                    spawn_missile(&mut commands, &assets, shooter_trans.translation, direction, Warhead {owner: *se, damage: weapon.damage, radius: CLUSTER_BLAST_RADIUS}, time.elapsed_seconds());
+                }
+            },
+            _ => ()
+        }
     }
 }
 
 // ---
 
-const BALL_RADIUS: f32 = 0.3;
+fn spawn_missile<'a>(
+    commands: &'a mut Commands,
+    assets: &MissileAssets,
+    origin: Vec3,
+    direction: Vec3,
+    warhead: Warhead,
+    now: f32
+) -> bevy::ecs::system::EntityCommands<'a> {
+    let mut missile = commands.spawn((
+        PbrBundle {
+            material: assets.material.clone(),
+            mesh: assets.mesh.clone(),
+            transform: Transform::from_translation(origin + direction * 15.).looking_to(direction, Vec3::Y),
+            ..default()
+        },
+        Missile,
+        warhead,
+        LifeTime(now + 10.),
+        RigidBody::Dynamic,
+        GravityScale(0.),
+        Collider::sphere(BALL_RADIUS),
+        projectile_layers(),
+        ExternalImpulse::new(direction * 5.),
+    ));
+    missile.with_children(|p| {
+        p.spawn((
+            ParticleEffectBundle {
+                effect: ParticleEffect::new(assets.trail.clone()),
+                transform: Transform::from_xyz(0., 0., -1.5 * BALL_RADIUS).with_rotation(Quat::from_rotation_x(f32::to_radians(90.))),
+                ..default()
+            },
+        ));
+    });
+    missile
+}
 
-fn shot(
+// ---
+
+fn guide(
     mut commands: Commands,
-    mut drone_q: Query<(&Transform, &mut Missiles), With<Manual>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut effects: ResMut<Assets<EffectAsset>>,
-    time: Res<Time>,
-    mut writer: EventWriter<UpdateWidgets>
+    mut missile_q: Query<(Entity, &mut Transform, &mut LinearVelocity, &mut Guidance), With<Missile>>,
+    target_q: Query<(&Transform, Option<&LinearVelocity>), Without<Missile>>,
+    time: Res<Time>
 ) {
-    if let Ok((drone_trans, mut missiles)) = drone_q.get_single_mut() {
-        if missiles.0 == 0  {
-            return;
+    let dt = time.delta_seconds();
+    for (me, mut mt, mut mv, mut guidance) in missile_q.iter_mut() {
+        let Ok((tt, otv)) = target_q.get(guidance.target) else {
+            commands.entity(me).remove::<Guidance>();
+            continue;
+        };
+        guidance.fuel -= dt;
+        if guidance.fuel <= 0. {
+            commands.entity(me).remove::<Guidance>();
+            continue;
         }
 
-        missiles.0 -= 1; 
-        writer.send(UpdateWidgets(vec![
-            WidgetUpdateData::from_key_value(I_MISSILES.0, missiles.0 as f32)
-        ])); 
-        commands.spawn((
-            PbrBundle {
-                material: materials.add(Color::srgba(0.3, 0.3, 0.3, 0.5)),
-                mesh: meshes.add(Sphere::new(BALL_RADIUS)),
-                transform: Transform::from_translation(drone_trans.translation + drone_trans.forward() * 15.).looking_to(drone_trans.forward(), Vec3::Y),
-                ..default()
-            },
-            Missile,
-            LifeTime(time.elapsed_seconds() + 10.),
-            RigidBody::Dynamic,
-            GravityScale(0.),
-            Collider::sphere(BALL_RADIUS),
-            ExternalImpulse::new(drone_trans.forward() * 5.),
-        ))
-        .with_children(|p| {
-            p.spawn((
-                ParticleEffectBundle {
-                    effect: ParticleEffect::new(effects.add(trail())),
-                    transform: Transform::from_xyz(0., 0., -1.5 * BALL_RADIUS).with_rotation(Quat::from_rotation_x(f32::to_radians(90.))),
-                    ..default()
-                },
-            ));
-        })
-        ;
+        let speed = mv.0.length();
+        if speed < f32::EPSILON {
+            continue;
+        }
+
+        let target_velocity = if let Some(tv) = otv {tv.0} else {Vec3::ZERO};
+        let accel = pn_acceleration(tt.translation - mt.translation, target_velocity - mv.0, mv.0, guidance.nav_const);
+
+        let current = mv.0 / speed;
+        let desired = (mv.0 + accel * dt).normalize_or_zero();
+        let direction = if desired == Vec3::ZERO {
+            current
+        } else {
+            let angle = current.angle_between(desired);
+            let max_angle = guidance.turn_rate * dt;
+            if angle > max_angle {
+                Quat::IDENTITY.slerp(Quat::from_rotation_arc(current, desired), max_angle / angle) * current
+            } else {
+                desired
+            }
+        };
+
+        mv.0 = direction * f32::min(speed + GUIDED_THRUST * dt, f32::max(speed, GUIDED_SPEED));
+        mt.look_to(direction, Vec3::Y);
     }
+}
 
+// ---
+
+fn pn_acceleration(los: Vec3, relative_velocity: Vec3, missile_velocity: Vec3, nav_const: f32) -> Vec3 {
+    let distance_squared = los.length_squared();
+    if distance_squared < f32::EPSILON {
+        return Vec3::ZERO;
+    }
+    let los_rate = los.cross(relative_velocity) / distance_squared;
+    nav_const * los_rate.cross(missile_velocity)
 }
 
 // ---
 
+const BLAST_IMPULSE: f32 = 20000.;
+
 fn destroy(
     mut commands: Commands,
     mut ev_reader: EventReader<MissileDestroy>,
-    t_q: Query<&Transform, Without<Blast>>,
-    mut b_q: Query<(&mut Transform, &mut EffectSpawner), With<Blast>>
+    mut fx_writer: EventWriter<PlayEffect>,
+    mut damage_writer: EventWriter<DamageEvent>,
+    spatial: SpatialQuery,
+    t_q: Query<(&Transform, &Warhead), With<Missile>>,
+    mut victim_q: Query<(&Transform, &Collider, Option<&mut ExternalImpulse>), Without<Missile>>,
 ) {
+    let mut detonated = Vec::new();
     for ev in ev_reader.read()   {
+        if detonated.contains(&ev.0) {
+            continue;
+        }
+        detonated.push(ev.0);
+        let Ok((ct, warhead)) = t_q.get(ev.0) else {
+            continue;
+        };
         commands.entity(ev.0).despawn_recursive();
-        if let  Ok(ct) = t_q.get(ev.0) {
-            if let Ok ((mut bt, mut bs)) = b_q.get_single_mut() {
-                bt.translation = ct.translation;
-                bs.reset();
+        let center = ct.translation;
+        fx_writer.send(PlayEffect(EffectKind::Blast, center));
+
+        for e in spatial.shape_intersections(
+            &Collider::sphere(warhead.radius),
+            center,
+            Quat::IDENTITY,
+            weapon_filter(warhead.owner)
+        ) {
+            let Ok((vt, vc, oi)) = victim_q.get_mut(e) else {
+                continue;
+            };
+            let falloff = blast_falloff(vc.distance_to_point(vt.translation, vt.rotation, center, true), warhead.radius);
+            if falloff <= 0. {
+                continue;
+            }
+            damage_writer.send(DamageEvent {
+                source: warhead.owner,
+                target: e,
+                amount: warhead.damage * falloff,
+                kind: DamageKind::Explosion,
+                point: center
+            });
+            if let Some(mut impulse) = oi {
+                impulse.apply_impulse((vt.translation - center).normalize_or_zero() * BLAST_IMPULSE * falloff);
             }
         }
     }
@@ -178,15 +368,45 @@ fn destroy(
 
 // ---
 
+fn blast_falloff(distance: f32, radius: f32) -> f32 {
+    (1. - distance / radius).clamp(0., 1.)
+}
+
+// ---
+
 fn collision(
-    mut collision_events: EventReader<CollisionEnded>,
-    e_q: Query<Entity, With<Missile>>,
+    mut hit_events: EventReader<MissileHit>,
     mut ev_writer: EventWriter<MissileDestroy>
 ) {
-    for CollisionEnded(e1, e2)  in  collision_events.read() {
-        for e0 in e_q.iter().filter(|e| {e == e1 || e == e2}) {
-            ev_writer.send(MissileDestroy(e0));
-            break;    
-        }
+    for hit in hit_events.read() {
+        ev_writer.send(MissileDestroy(hit.missile));
     }    
-}
\ No newline at end of file
+}
+
+// ---
+
+fn rearm(
+    mut reader: EventReader<DroneEvent>,
+    mut drone_q: Query<&mut Missiles>,
+) {
+    for e in reader.read() {
+        if let DroneEvent::SupplyMissiles((de, v)) = e {
+            if let Ok(mut missiles) = drone_q.get_mut(*de) {
+                missiles.gain(*v);
+            }
+        }
+    }
+}
+
+// ---
+
+fn update_indicators(
+    drone_q: Query<&Missiles, (With<Manual>, Changed<Missiles>)>,
+    mut writer: EventWriter<UpdateWidgets>
+) {
+    if let Ok(missiles) = drone_q.get_single() {
+        writer.send(UpdateWidgets(vec![
+            WidgetUpdateData::from_key_value(I_MISSILES.0, missiles.get() as f32)
+        ]));
+    }
+}
@@ -0,0 +1,175 @@
+use bevy::prelude::*;
+use avian3d::prelude::*;
+
+use crate::LifeTime;
+use crate::layers::sensor_layers;
+use crate::drone::DroneEvent;
+use crate::mining::{Cargo, Ore, Yield};
+use crate::collision_routing::PickupContact;
+
+pub struct PickupsPlugin;
+impl Plugin for PickupsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, startup);
+        app.add_systems(Update, scatter.run_if(on_event::<Yield>()));
+        app.add_systems(Update, pick.run_if(on_event::<PickupContact>()));
+    }
+}
+
+// ---
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PickupKind {
+    Ore(Ore, f32),
+    Fluel(f32),
+    Missiles(f32)
+}
+
+// Floating loot, collected by flying through it
+
+#[derive(Component)]
+pub struct Pickup(PickupKind);
+
+#[derive(Resource)]
+pub struct PickupAssets {
+    chunk: Handle<Mesh>,
+    canister: Handle<Mesh>,
+    ammo_box: Handle<Mesh>,
+    ores: [Handle<StandardMaterial>; 3],
+    fluel: Handle<StandardMaterial>,
+    missiles: Handle<StandardMaterial>
+}
+
+const PICKUP_RADIUS: f32 = 3.;
+const PICKUP_LIFETIME: f32 = 60.;
+const PICKUP_SPREAD_SPEED: f32 = 3.;
+const ORE_CHUNK_SIZE: f32 = 5.;            // ore units per chunk
+const ORE_CHUNKS_MAX: usize = 4;           // per ore, larger yields make heavier chunks
+const FLUEL_CANISTER_CHANCE: f32 = 0.15;
+const FLUEL_CANISTER: f32 = 30.;
+const MISSILE_CRATE_CHANCE: f32 = 0.1;
+const MISSILE_CRATE: f32 = 3.;
+
+// ---
+
+fn startup(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let mut glowing = |base: Color, emissive: LinearRgba| materials.add(StandardMaterial {
+        base_color: base,
+        emissive,
+        ..default()
+    });
+    commands.insert_resource(PickupAssets {
+        chunk: meshes.add(Sphere::new(0.8)),
+        canister: meshes.add(Cylinder::new(0.6, 1.6)),
+        ammo_box: meshes.add(Cuboid::new(1.4, 1., 1.4)),
+        ores: [
+            glowing(Color::srgb(0.7, 0.9, 1.), LinearRgba::rgb(0.5, 1., 2.)),
+            glowing(Color::srgb(0.6, 0.6, 0.65), LinearRgba::rgb(0.8, 0.8, 0.9)),
+            glowing(Color::srgb(0.7, 0.3, 1.), LinearRgba::rgb(2., 0.5, 3.)),
+        ],
+        fluel: glowing(Color::srgb(1., 0.9, 0.2), LinearRgba::rgb(2., 1.6, 0.2)),
+        missiles: glowing(Color::srgb(1., 0.3, 0.2), LinearRgba::rgb(2., 0.4, 0.2)),
+    });
+}
+
+// ---
+
+fn scatter(
+    mut commands: Commands,
+    mut reader: EventReader<Yield>,
+    assets: Res<PickupAssets>,
+    time: Res<Time>,
+) {
+    for ev in reader.read() {
+        let mut kinds = Vec::new();
+        for (ore, amount) in [Ore::Ice, Ore::Metal, Ore::Rare].into_iter().zip(ev.amounts) {
+            if amount <= 0. {
+                continue;
+            }
+            let chunks = ((amount / ORE_CHUNK_SIZE).ceil() as usize).clamp(1, ORE_CHUNKS_MAX);
+            kinds.extend((0 .. chunks).map(|_| PickupKind::Ore(ore, amount / chunks as f32)));
+        }
+        if fastrand::f32() < FLUEL_CANISTER_CHANCE {
+            kinds.push(PickupKind::Fluel(FLUEL_CANISTER));
+        }
+        if fastrand::f32() < MISSILE_CRATE_CHANCE {
+            kinds.push(PickupKind::Missiles(MISSILE_CRATE));
+        }
+
+        for kind in kinds {
+            let (mesh, material) = match kind {
+                PickupKind::Ore(ore, _) => (assets.chunk.clone(), assets.ores[ore as usize].clone()),
+                PickupKind::Fluel(_) => (assets.canister.clone(), assets.fluel.clone()),
+                PickupKind::Missiles(_) => (assets.ammo_box.clone(), assets.missiles.clone())
+            };
+            let dir = Vec3::new(fastrand::f32() - 0.5, fastrand::f32() - 0.5, fastrand::f32() - 0.5).normalize_or_zero();
+            commands.spawn((
+                PbrBundle {
+                    mesh,
+                    material,
+                    transform: Transform::from_translation(ev.point + dir * PICKUP_RADIUS),
+                    ..default()
+                },
+                Pickup(kind),
+                LifeTime(time.elapsed_seconds() + PICKUP_LIFETIME),
+                RigidBody::Dynamic,
+                GravityScale(0.),
+                Sensor,
+                Collider::sphere(PICKUP_RADIUS),
+                sensor_layers(),
+                LinearDamping(0.3),
+                LinearVelocity(ev.velocity + dir * PICKUP_SPREAD_SPEED),
+                AngularVelocity(dir),
+            ));
+        }
+    }
+}
+
+// ---
+
+// Ore goes into the hold as far as it fits, the rest keeps floating
+
+fn pick(
+    mut commands: Commands,
+    mut reader: EventReader<PickupContact>,
+    mut pickup_q: Query<&mut Pickup>,
+    mut cargo_q: Query<&mut Cargo>,
+    mut drone_writer: EventWriter<DroneEvent>,
+) {
+    // a pickup touched twice in one frame is only given once
+    let mut taken_now = Vec::new();
+    for ev in reader.read() {
+        if taken_now.contains(&ev.pickup) {
+            continue;
+        }
+        let Ok(mut pickup) = pickup_q.get_mut(ev.pickup) else {
+            continue;
+        };
+        let taken = match pickup.0 {
+            PickupKind::Ore(ore, amount) => {
+                let Ok(mut cargo) = cargo_q.get_mut(ev.drone) else {
+                    continue;
+                };
+                let left = amount - cargo.add(ore, amount);
+                pickup.0 = PickupKind::Ore(ore, left);
+                left <= 0.
+            },
+            PickupKind::Fluel(v) => {
+                drone_writer.send(DroneEvent::SupplyFluel((ev.drone, v)));
+                true
+            },
+            PickupKind::Missiles(v) => {
+                drone_writer.send(DroneEvent::SupplyMissiles((ev.drone, v)));
+                true
+            }
+        };
+        if taken {
+            taken_now.push(ev.pickup);
+            commands.entity(ev.pickup).despawn_recursive();
+        }
+    }
+}
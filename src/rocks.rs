@@ -0,0 +1,111 @@
+use bevy::prelude::*;
+use bevy::render::mesh::VertexAttributeValues;
+use avian3d::prelude::*;
+
+use crate::field::range;
+
+// Procedural asteroid shapes: an icosphere pushed in and out by fractal value noise.
+// The same seed always gives the same rock
+
+#[derive(Clone, Copy, Debug)]
+pub struct RockParams {
+    pub radius: f32,
+    pub subdivisions: usize,
+    pub roughness: f32,         // displacement, fraction of the radius
+    pub frequency: f32,         // noise features per radius
+    pub octaves: usize,
+    pub stretch: (f32, f32)     // per axis elongation range
+}
+
+pub const ROCK: RockParams = RockParams {
+    radius: 10.,
+    subdivisions: 3,
+    roughness: 0.35,
+    frequency: 1.6,
+    octaves: 3,
+    stretch: (0.75, 1.25)
+};
+
+// ---
+
+// Flat shaded mesh and a convex hull collider built from the same vertices
+
+pub fn rock(seed: u64, params: &RockParams) -> (Mesh, Collider) {
+    let mut rng = fastrand::Rng::with_seed(seed);
+    let stretch = Vec3::new(
+        range(&mut rng, params.stretch.0, params.stretch.1),
+        range(&mut rng, params.stretch.0, params.stretch.1),
+        range(&mut rng, params.stretch.0, params.stretch.1)
+    );
+    let offset = Vec3::new(rng.f32(), rng.f32(), rng.f32()) * 100.;
+
+    let mut mesh = Sphere::new(params.radius).mesh().ico(params.subdivisions).expect("too many icosphere subdivisions");
+    let mut points = Vec::new();
+    if let Some(VertexAttributeValues::Float32x3(positions)) = mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION) {
+        for p in positions.iter_mut() {
+            let n = Vec3::from(*p).normalize();
+            let d = 1. + params.roughness * (fbm(seed, offset + n * params.frequency, params.octaves) * 2. - 1.);
+            let v = n * params.radius * d * stretch;
+            *p = v.to_array();
+            points.push(v);
+        }
+    }
+    mesh.duplicate_vertices();
+    mesh.compute_flat_normals();
+
+    let collider = Collider::convex_hull(points).unwrap_or_else(|| Collider::sphere(params.radius));
+    (mesh, collider)
+}
+
+// ---
+
+// 0..1, octaves of value noise with halving amplitude
+
+fn fbm(seed: u64, p: Vec3, octaves: usize) -> f32 {
+    let mut sum = 0.;
+    let mut amplitude = 1.;
+    let mut total = 0.;
+    let mut frequency = 1.;
+    for o in 0 .. octaves {
+        sum += value_noise(seed.wrapping_add(o as u64), p * frequency) * amplitude;
+        total += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.;
+    }
+    sum / total
+}
+
+// ---
+
+fn value_noise(seed: u64, p: Vec3) -> f32 {
+    let cell = p.floor();
+    let f = p - cell;
+    let t = f * f * (Vec3::splat(3.) - 2. * f);
+    let (x, y, z) = (cell.x as i32, cell.y as i32, cell.z as i32);
+
+    let corner = |dx: i32, dy: i32, dz: i32| hash(seed, x + dx, y + dy, z + dz);
+    let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), t.x);
+    let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), t.x);
+    let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), t.x);
+    let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), t.x);
+    lerp(lerp(x00, x10, t.y), lerp(x01, x11, t.y), t.z)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+// ---
+
+fn hash(seed: u64, x: i32, y: i32, z: i32) -> f32 {
+    let mut h = seed
+        ^ (x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F)
+        ^ (z as u64).wrapping_mul(0x1656_67B1_9E37_79F9);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xC4CE_B9FE_1A85_EC53);
+    h ^= h >> 33;
+    (h >> 40) as f32 / (1u64 << 24) as f32
+}
@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+use crate::{GameState, NotReady};
+use crate::asteroids::Asteroid;
+use crate::damage::{DamageSystems, Died};
+use crate::drone::Manual;
+use crate::ui::{RegisterWidgets, ULayout, UpdateWidgets, WType, WidgetRegData, WidgetUpdateData};
+
+pub struct ScorePlugin;
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Score>();
+        app.add_systems(Startup, spawn);
+        app.add_systems(Update, setup.run_if(in_state(GameState::Setup)));
+        app.add_systems(Update, count.after(DamageSystems).run_if(on_event::<Died>()));
+    }
+}
+
+// ---
+
+#[derive(Resource, Default)]
+pub struct Score {
+    pub kills: u32
+}
+
+#[derive(Component)]
+pub struct ScoreTempMarker;
+
+const I_SCORE: (&str, &str) = ("score", "Score");
+
+// ---
+
+fn spawn(
+    mut commands: Commands,
+) {
+    commands.spawn((NotReady, ScoreTempMarker));
+}
+
+// ---
+
+fn setup (
+    mut commands: Commands,
+    check_q: Query<Entity, (With<NotReady>, With<ScoreTempMarker>)>,
+    mut writer: EventWriter<RegisterWidgets>,
+) {
+    let Ok(e) = check_q.get_single() else {
+        return;
+    };
+    writer.send(
+        RegisterWidgets(
+            vec![
+                WidgetRegData {
+                    key: I_SCORE.0,
+                    label: I_SCORE.1,
+                    parent: ULayout::Footer,
+                    wtype: WType::Integer,
+                    image: None,
+                    start: 4,
+                    span: 2,
+                    default: Some(0.)
+                },
+            ]
+        )
+    );
+    commands.entity(e).despawn();
+}
+
+// ---
+
+fn count(
+    mut reader: EventReader<Died>,
+    mut score: ResMut<Score>,
+    asteroids_q: Query<Entity, With<Asteroid>>,
+    player_q: Query<Entity, With<Manual>>,
+    mut writer: EventWriter<UpdateWidgets>
+) {
+    for ev in reader.read() {
+        if asteroids_q.contains(ev.entity) && player_q.contains(ev.source) {
+            score.kills += 1;
+            writer.send(UpdateWidgets(vec![
+                WidgetUpdateData::from_key_value(I_SCORE.0, score.kills as f32)
+            ]));
+        }
+    }
+}
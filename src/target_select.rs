@@ -4,13 +4,17 @@ use bevy::window::PrimaryWindow;
 // use bevy_rapier3d::prelude::*;
 use avian3d::prelude::*;
 
-use crate::camera::Cam;
-use crate::Target;
+use crate::camera::{Cam, Focus};
+use crate::asteroids::Asteroid;
+use crate::damage::{DamageSystems, Died};
+use crate::{Health, Target};
+use crate::layers::pick_filter;
 
 pub struct TargetSelectPlugin;
 impl Plugin for TargetSelectPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Update, mouse_click.run_if(on_event::<MouseButtonInput>()));
+        app.add_systems(Update, retarget.after(DamageSystems).run_if(on_event::<Died>()));
      }
 }
 
@@ -54,7 +58,7 @@ fn mouse_click(
             ray.direction.into(),
             f32::MAX,
             true, 
-            SpatialQueryFilter::default()
+            pick_filter()
         ) {
             if let Ok(old_target) = old_target_q.get_single() {
                 commands.entity(old_target).remove::<Target>();
@@ -64,3 +68,35 @@ fn mouse_click(
     }
 
 }
+
+// ---
+
+fn retarget(
+    mut reader: EventReader<Died>,
+    mut commands: Commands,
+    target_q: Query<Entity, With<Target>>,
+    focus_q: Query<&Transform, With<Focus>>,
+    candidates_q: Query<(Entity, &Transform, &Health), (With<Asteroid>, Without<Target>)>,
+) {
+    let Ok(focus_trans) = focus_q.get_single() else {
+        return;
+    };
+    for ev in reader.read() {
+        if !target_q.contains(ev.entity) {
+            continue;
+        }
+        let mut candidate = Entity::PLACEHOLDER;
+        let mut min_distance =  f32::MAX;
+        for (ce, ct, ch) in candidates_q.iter() {
+            let distance = focus_trans.translation.distance_squared(ct.translation);
+            if !ch.is_dead() && distance < min_distance {
+                min_distance = distance;
+                candidate = ce;
+            }
+        }
+        if candidate != Entity::PLACEHOLDER {
+            commands.entity(ev.entity).remove::<Target>();
+            commands.entity(candidate).insert(Target);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+use avian3d::prelude::*;
+
+use crate::{GameState, NotReady, Target};
+use crate::beam::{fire, spawn_beam, Beam, BeamAssets};
+use crate::drone::{Drone, Fluel, Manual};
+use crate::weapon::{pay, WeaponCost};
+use crate::gravity::add_force;
+
+pub struct TractorPlugin;
+impl Plugin for TractorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn);
+        app.add_systems(Update, setup.run_if(in_state(GameState::Setup)));
+        app.add_systems(PhysicsSchedule, pull.in_set(PhysicsStepSet::First).run_if(in_state(GameState::Game)));
+    }
+}
+
+// ---
+
+// Spring between the ship and its target while T is held.
+// The spring works on accelerations, so anything light enough is towed the same way.
+// The ship feels the reaction, towing something heavy drags the ship along too.
+// Runs with the physics steps like gravity, so the pull doesn't depend on the frame rate
+
+#[derive(Component)]
+pub struct Tractor {
+    beam: Entity
+}
+
+#[derive(Component)]
+pub struct TractorTempMarker;
+
+const TRACTOR_RANGE: f32 = 120.;
+const TRACTOR_REST_LENGTH: f32 = 25.;
+const TRACTOR_STIFFNESS: f32 = 0.8;      // acceleration per unit of stretch
+const TRACTOR_DAMPING: f32 = 1.5;        // acceleration per unit of closing speed
+const TRACTOR_MAX_MASS: f32 = 600.;      // pickups and small asteroids only
+const TRACTOR_COST: f32 = 8.;            // fluel per second
+const TRACTOR_BEAM_WIDTH: f32 = 0.4;
+
+// ---
+
+fn spawn(
+    mut commands: Commands,
+) {
+    commands.spawn((NotReady, TractorTempMarker));
+}
+
+// ---
+
+fn setup (
+    mut commands: Commands,
+    drones_q: Query<Entity, (With<Drone>, Without<Tractor>)>,
+    check_q: Query<Entity, (With<NotReady>, With<TractorTempMarker>)>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    beam_assets: Res<BeamAssets>,
+) {
+    if drones_q.is_empty() {
+        if let Ok(e) = check_q.get_single() {
+            commands.entity(e).despawn();
+        }
+        return;
+    }
+    for de in drones_q.iter() {
+        let beam = spawn_beam(&mut commands, &beam_assets, &mut materials, LinearRgba::rgb(0.3, 0.5, 1.));
+        commands.entity(de).insert(Tractor {beam});
+    }
+}
+
+// ---
+
+fn pull(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut drone_q: Query<(Entity, &Transform, &LinearVelocity, &Tractor, &mut Fluel, Option<&mut ExternalForce>), With<Manual>>,
+    mut target_q: Query<(Entity, &Transform, &LinearVelocity, &RigidBody, &Mass, Option<&mut ExternalForce>), (With<Target>, Without<Manual>)>,
+    mut beam_q: Query<(&mut Beam, &mut Transform, &mut Visibility), (Without<Manual>, Without<Target>)>,
+    time: Res<Time<Physics>>
+) {
+    if !keys.pressed(KeyCode::KeyT) {
+        return;
+    }
+    let Ok((de, dt, dv, tractor, fluel, dforce)) = drone_q.get_single_mut() else {
+        return;
+    };
+    let Ok((te, tt, tv, body, mass, tforce)) = target_q.get_single_mut() else {
+        return;
+    };
+    let span = tt.translation - dt.translation;
+    let distance = span.length();
+    if *body != RigidBody::Dynamic || mass.0 > TRACTOR_MAX_MASS || distance > TRACTOR_RANGE || distance < f32::EPSILON {
+        return;
+    }
+    if !pay(WeaponCost::Energy(TRACTOR_COST * time.delta_seconds()), None, Some(fluel)) {
+        return;
+    }
+
+    let dir = span / distance;
+    let closing = (tv.0 - dv.0).dot(dir);
+    let force = -dir * mass.0 * (TRACTOR_STIFFNESS * (distance - TRACTOR_REST_LENGTH) + TRACTOR_DAMPING * closing);
+
+    add_force(&mut commands, te, tforce, force);
+    add_force(&mut commands, de, dforce, -force);
+
+    if let Ok((mut beam, mut bt, mut bv)) = beam_q.get_mut(tractor.beam) {
+        fire(&mut beam, &mut bt, &mut bv, dt.translation + dt.forward() * 3., tt.translation, TRACTOR_BEAM_WIDTH);
+    }
+}
@@ -0,0 +1,378 @@
+use std::cmp::Ordering;
+use bevy::prelude::*;
+use avian3d::prelude::*;
+
+use crate::{GameState, NotReady};
+use crate::asteroids::{Asteroid, AsteroidSize};
+use crate::beam::{spawn_beam, BeamAssets};
+use crate::docks::Dock;
+use crate::laser::{LaserBeams, LaserHeat};
+use crate::layers::weapon_filter;
+use crate::weapon::{Aim, Armory, Projectile, Weapon, WeaponCost, WeaponShot};
+use crate::ui::{RegisterWidgets, ULayout, UpdateWidgets, WType, WidgetRegData, WidgetUpdateData};
+
+pub struct TurretPlugin;
+impl Plugin for TurretPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TurretOrders>();
+        app.add_systems(Startup, startup);
+        app.add_systems(Update, setup.run_if(in_state(GameState::Setup)));
+        app.add_systems(Update, (input, select, track, update_indicators).chain().run_if(in_state(GameState::Game)));
+        app.add_systems(Update, dismantle);
+    }
+}
+
+// ---
+
+// Which asteroid in range a turret goes for first
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum TargetPriority {
+    #[default]
+    Threat,
+    Nearest,
+    Largest
+}
+
+impl TargetPriority {
+    fn next(&self) -> Self {
+        match self {
+            Self::Threat => Self::Nearest,
+            Self::Nearest => Self::Largest,
+            Self::Largest => Self::Threat
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Threat => "Threat",
+            Self::Nearest => "Nearest",
+            Self::Largest => "Largest"
+        }
+    }
+
+    // lower goes first, the distance decides between equals
+
+    fn rank(&self, span: Vec3, velocity: Vec3, size: &AsteroidSize) -> f32 {
+        match self {
+            Self::Threat => {
+                // seconds until it reaches the turret, anything drifting away comes last
+                let closing = -velocity.dot(span.normalize_or_zero());
+                if closing > 0. {span.length() / closing} else {f32::MAX}
+            },
+            Self::Nearest => 0.,
+            Self::Largest => -size.health_factor()
+        }
+    }
+}
+
+// Everything a turret is built with, the rate of fire comes with the weapon
+
+#[derive(Clone, Copy, Debug)]
+pub struct TurretSpec {
+    pub weapon: Weapon,
+    pub range: f32,
+    pub slew_rate: f32,                        // radians per second
+    pub priority: Option<TargetPriority>       // None follows the orders given to all turrets
+}
+
+// A gun head turning on its own towards the asteroid it picked.
+// Turrets run off the base power, their shots cost nothing
+
+#[derive(Component)]
+pub struct Turret {
+    range: f32,
+    slew_rate: f32,
+    priority: TargetPriority,
+    fixed: bool
+}
+
+// Beams are drawn in world space and can't be children, they follow the turret out by this
+
+#[derive(Component)]
+pub struct TurretBeam(Entity);
+
+// Priority given to all turrets without one of their own, switched from the keyboard
+
+#[derive(Resource, Default)]
+pub struct TurretOrders {
+    priority: TargetPriority
+}
+
+#[derive(Resource)]
+pub struct TurretAssets {
+    head: Handle<Mesh>,
+    barrel: Handle<Mesh>,
+    material: Handle<StandardMaterial>
+}
+
+#[derive(Component)]
+pub struct TurretTempMarker;
+
+pub const TURRET_LASER: Weapon = Weapon {
+    name: "Turret laser",
+    projectile: Projectile::Beam,
+    fire_rate: 30.,
+    cost: WeaponCost::Energy(0.),
+    damage: 2.,
+    range: 250.
+};
+
+pub const TURRET_MISSILE: Weapon = Weapon {
+    name: "Turret missile",
+    projectile: Projectile::Missile,
+    fire_rate: 0.4,
+    cost: WeaponCost::Ammo(0),
+    damage: 10.,
+    range: 500.
+};
+
+// missiles are slow to reload, they are kept for the big rocks
+pub const LASER_TURRET: TurretSpec = TurretSpec {weapon: TURRET_LASER, range: 250., slew_rate: 1.5, priority: None};
+pub const MISSILE_TURRET: TurretSpec = TurretSpec {weapon: TURRET_MISSILE, range: 450., slew_rate: 0.8, priority: Some(TargetPriority::Largest)};
+
+const TURRET_HEAD_RADIUS: f32 = 1.5;
+const TURRET_BARREL_LENGTH: f32 = 4.;
+const TURRET_LASER_SPREAD: f32 = 0.6;
+const TURRET_AIM_TOLERANCE: f32 = 0.05;    // radians off the target still worth a shot
+const DOCK_TURRET_EVERY: usize = 4;        // every n-th dock gets a laser turret
+const DOCK_TURRET_HEIGHT: f32 = 7.;
+
+// free standing sentries around the base
+const SENTRY_POSITIONS: [Vec3; 2] = [
+    Vec3::new(90., 40., 90.),
+    Vec3::new(-90., 40., -90.),
+];
+
+const I_TURRETS: (&str, &str) = ("turrets", "Turrets");
+
+// ---
+
+fn startup(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    commands.spawn((NotReady, TurretTempMarker));
+    commands.insert_resource(TurretAssets {
+        head: meshes.add(Sphere::new(TURRET_HEAD_RADIUS)),
+        barrel: meshes.add(Cuboid::new(0.6, 0.6, TURRET_BARREL_LENGTH)),
+        material: materials.add(Color::srgb(0.35, 0.37, 0.4)),
+    });
+}
+
+// ---
+
+fn setup (
+    mut commands: Commands,
+    check_q: Query<Entity, (With<NotReady>, With<TurretTempMarker>)>,
+    docks_q: Query<Entity, With<Dock>>,
+    assets: Res<TurretAssets>,
+    beam_assets: Res<BeamAssets>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut writer: EventWriter<RegisterWidgets>,
+) {
+    let Ok(e) = check_q.get_single() else {
+        return;
+    };
+    for dock in docks_q.iter().step_by(DOCK_TURRET_EVERY) {
+        let turret = spawn_turret(&mut commands, &assets, &beam_assets, &mut materials, Transform::from_xyz(0., DOCK_TURRET_HEIGHT, 0.), &LASER_TURRET);
+        commands.entity(dock).add_child(turret);
+    }
+    for position in SENTRY_POSITIONS {
+        spawn_turret(&mut commands, &assets, &beam_assets, &mut materials, Transform::from_translation(position), &MISSILE_TURRET);
+    }
+    writer.send(
+        RegisterWidgets(
+            vec![
+                WidgetRegData {
+                    key: I_TURRETS.0,
+                    label: I_TURRETS.1,
+                    parent: ULayout::Content,
+                    wtype: WType::Text,
+                    image: None,
+                    start: 1,
+                    span: 1,
+                    default: None
+                },
+            ]
+        )
+    );
+    commands.entity(e).despawn();
+}
+
+// ---
+
+// The transform is local when the turret is mounted on something, the caller adds it as a child
+
+pub fn spawn_turret(
+    commands: &mut Commands,
+    assets: &TurretAssets,
+    beam_assets: &BeamAssets,
+    materials: &mut Assets<StandardMaterial>,
+    transform: Transform,
+    spec: &TurretSpec
+) -> Entity {
+    let turret = commands.spawn((
+        PbrBundle {
+            mesh: assets.head.clone(),
+            material: assets.material.clone(),
+            transform,
+            ..default()
+        },
+        Name::new("Turret"),
+        Turret {
+            range: spec.range,
+            slew_rate: spec.slew_rate,
+            priority: spec.priority.unwrap_or_default(),
+            fixed: spec.priority.is_some()
+        },
+        Armory::new(vec![spec.weapon]),
+    ))
+    .with_children(|p| {
+        p.spawn(PbrBundle {
+            mesh: assets.barrel.clone(),
+            material: assets.material.clone(),
+            transform: Transform::from_xyz(0., 0., -TURRET_BARREL_LENGTH / 2.),
+            ..default()
+        });
+    })
+    .id();
+
+    if spec.weapon.projectile == Projectile::Beam {
+        let beams = [0, 1].map(|_| {
+            let beam = spawn_beam(commands, beam_assets, materials, LinearRgba::rgb(1., 0.3, 0.2));
+            commands.entity(beam).insert(TurretBeam(turret));
+            beam
+        });
+        commands.entity(turret).insert((LaserBeams::new(beams, TURRET_LASER_SPREAD), LaserHeat::default()));
+    }
+    turret
+}
+
+// ---
+
+fn input(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut orders: ResMut<TurretOrders>,
+    mut turrets_q: Query<&mut Turret>,
+) {
+    if keys.just_pressed(KeyCode::KeyY) {
+        orders.priority = orders.priority.next();
+    }
+
+    // turrets built later follow the orders given before
+
+    for mut turret in turrets_q.iter_mut() {
+        if turret.fixed {
+            continue;
+        }
+        if orders.is_changed() || turret.is_added() {
+            turret.priority = orders.priority;
+        }
+    }
+}
+
+// ---
+
+fn select(
+    mut commands: Commands,
+    turrets_q: Query<(Entity, &GlobalTransform, &Turret, Option<&Aim>)>,
+    asteroids_q: Query<(Entity, &Transform, &LinearVelocity, &AsteroidSize), With<Asteroid>>,
+) {
+    for (te, tt, turret, aim) in turrets_q.iter() {
+        let position = tt.translation();
+        let best = asteroids_q.iter()
+            .filter_map(|(ae, at, av, size)| {
+                let span = at.translation - position;
+                let distance = span.length();
+                (distance <= turret.range).then(|| (ae, turret.priority.rank(span, av.0, size), distance))
+            })
+            .min_by(|a, b| (a.1, a.2).partial_cmp(&(b.1, b.2)).unwrap_or(Ordering::Equal))
+            .map(|(ae, ..)| ae);
+
+        match (best, aim) {
+            (Some(ae), Some(a)) if a.0 == ae => (),
+            (Some(ae), _) => {
+                commands.entity(te).insert(Aim(ae));
+            },
+            (None, Some(_)) => {
+                commands.entity(te).remove::<Aim>();
+            },
+            (None, None) => ()
+        }
+    }
+}
+
+// ---
+
+// Turn towards the aim no faster than the slew rate, fire once lined up and nothing else is in the way
+
+fn track(
+    mut turrets_q: Query<(Entity, &GlobalTransform, &mut Transform, &Turret, &mut Armory, Option<&Aim>)>,
+    targets_q: Query<&GlobalTransform, Without<Turret>>,
+    spatial: SpatialQuery,
+    mut shot_writer: EventWriter<WeaponShot>,
+    time: Res<Time>
+) {
+    for (te, tg, mut tt, turret, mut armory, aim) in turrets_q.iter_mut() {
+        // whatever is left of a beam hold is dropped, there is nothing to fire it at
+        let Some((target, target_t)) = aim.and_then(|a| targets_q.get(a.0).ok().map(|t| (a.0, t))) else {
+            armory.release();
+            continue;
+        };
+        let (_, current, position) = tg.to_scale_rotation_translation();
+        let Ok(dir) = Dir3::new(target_t.translation() - position) else {
+            continue;
+        };
+        let desired = Transform::IDENTITY.looking_to(dir, Vec3::Y).rotation;
+        let error = current.angle_between(desired);
+        let step = turret.slew_rate * time.delta_seconds();
+        let turned = if error <= step {desired} else {current.slerp(desired, step / error)};
+
+        // the slew is done in world space, the mount may be turned any way
+        let mount = current * tt.rotation.inverse();
+        tt.rotation = mount.inverse() * turned;
+
+        let clear = spatial.cast_ray(position, dir, turret.range, true, weapon_filter(te))
+            .map_or(true, |hit| hit.entity == target);
+        if error - step > TURRET_AIM_TOLERANCE || !clear {
+            armory.release();
+            continue;
+        }
+        if let Some(duration) = armory.trigger(time.elapsed_seconds(), time.delta_seconds()) {
+            shot_writer.send(WeaponShot(te, *armory.current(), duration));
+        }
+    }
+}
+
+// ---
+
+// A turret goes with whatever it was mounted on, its beams go with it
+
+fn dismantle(
+    mut commands: Commands,
+    mut removals: RemovedComponents<Turret>,
+    beams_q: Query<(Entity, &TurretBeam)>,
+) {
+    for turret in removals.read() {
+        for (be, owner) in beams_q.iter() {
+            if owner.0 == turret {
+                commands.entity(be).despawn_recursive();
+            }
+        }
+    }
+}
+
+// ---
+
+fn update_indicators(
+    orders: Res<TurretOrders>,
+    mut writer: EventWriter<UpdateWidgets>
+) {
+    if orders.is_changed() {
+        writer.send(UpdateWidgets(vec![
+            WidgetUpdateData::from_key_text(I_TURRETS.0, orders.priority.label())
+        ]));
+    }
+}
@@ -32,16 +32,21 @@ pub struct RegisterWidgets(pub Vec<WidgetRegData>);
 pub struct WidgetUpdateData {
     key: &'static str,
     color: Option<Color>,
-    value: f32
+    value: f32,
+    text: Option<&'static str>
 }
 
 impl WidgetUpdateData {
     pub fn from_key_value(key: &'static str, value: f32) -> Self {
-        Self {key, value, color: None}
+        Self {key, value, color: None, text: None}
     }
 
     pub fn from_key_value_color(key: &'static str, value: f32, color: Color) -> Self {
-        Self {key, value, color: Some(color)}
+        Self {key, value, color: Some(color), text: None}
+    }
+
+    pub fn from_key_text(key: &'static str, text: &'static str) -> Self {
+        Self {key, value: 0., color: None, text: Some(text)}
     }
 }
 
@@ -67,7 +72,7 @@ pub enum ULayout {
 
 #[derive(Component, PartialEq, Debug, Clone, Copy)]
 pub enum WType {
-    // Text,
+    Text,
     Float,
     Integer,
     Image
@@ -270,7 +275,7 @@ fn update_widget(
                         // text.sections[0].value = format!("{:.2}", w.value);
                         
                         // text.sections[0].value = if  *wtype == WType::Float { format!("{:.2}", w.value)} else {format!("{}", w.value)};
-                        text.sections[0].value = format_value(wtype, w.value);
+                        text.sections[0].value = if let Some(t) = w.text {t.to_string()} else {format_value(wtype, w.value)};
 
                         if let Some(c) = w.color {
                             text.sections[0].style.color = c;                        
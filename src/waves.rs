@@ -0,0 +1,143 @@
+use bevy::prelude::*;
+use crate::{GameState, NotReady};
+use crate::asteroids::{spawn_asteroid, MatMeshes};
+use crate::field::{wave, FieldConfig};
+use crate::ui::{RegisterWidgets, ULayout, UpdateWidgets, WType, WidgetRegData, WidgetUpdateData};
+
+pub struct WavesPlugin;
+impl Plugin for WavesPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Waves {
+            wave: 0,
+            phase: WavePhase::Calm,
+            timer: Timer::from_seconds(WAVE_CALM, TimerMode::Once)
+        });
+        app.add_systems(Startup, spawn);
+        app.add_systems(Update, setup.run_if(in_state(GameState::Setup)));
+        app.add_systems(Update, (schedule, update_indicators).chain().run_if(in_state(GameState::Game)));
+    }
+}
+
+// ---
+
+#[derive(PartialEq, Debug)]
+pub enum WavePhase {
+    Calm,
+    Active
+}
+
+#[derive(Resource)]
+pub struct Waves {
+    pub wave: u32,
+    pub phase: WavePhase,
+    timer: Timer
+}
+
+// Asteroids of the current wave, it's over when all of them are gone or time is up
+
+#[derive(Component)]
+pub struct WaveMember;
+
+#[derive(Component)]
+pub struct WavesTempMarker;
+
+const WAVE_CALM: f32 = 20.;
+const WAVE_MAX_DURATION: f32 = 90.;
+
+const I_WAVE: (&str, &str) = ("wave", "Wave");
+const I_NEXT_WAVE: (&str, &str) = ("next", "Next");
+
+// ---
+
+fn spawn(
+    mut commands: Commands,
+) {
+    commands.spawn((NotReady, WavesTempMarker));
+}
+
+// ---
+
+fn setup (
+    mut commands: Commands,
+    check_q: Query<Entity, (With<NotReady>, With<WavesTempMarker>)>,
+    mut writer: EventWriter<RegisterWidgets>,
+) {
+    let Ok(e) = check_q.get_single() else {
+        return;
+    };
+    writer.send(
+        RegisterWidgets(
+            vec![
+                WidgetRegData {
+                    key: I_WAVE.0,
+                    label: I_WAVE.1,
+                    parent: ULayout::Footer,
+                    wtype: WType::Integer,
+                    image: None,
+                    start: 6,
+                    span: 2,
+                    default: Some(0.)
+                },
+                WidgetRegData {
+                    key: I_NEXT_WAVE.0,
+                    label: I_NEXT_WAVE.1,
+                    parent: ULayout::Footer,
+                    wtype: WType::Integer,
+                    image: None,
+                    start: 8,
+                    span: 2,
+                    default: None
+                },
+            ]
+        )
+    );
+    commands.entity(e).despawn();
+}
+
+// ---
+
+fn schedule(
+    mut commands: Commands,
+    mut waves: ResMut<Waves>,
+    config: Res<FieldConfig>,
+    mm: Res<MatMeshes>,
+    members_q: Query<Entity, With<WaveMember>>,
+    time: Res<Time>
+) {
+    waves.timer.tick(time.delta());
+    match waves.phase {
+        WavePhase::Calm => {
+            if waves.timer.finished() {
+                waves.wave += 1;
+                for spec in wave(&config, waves.wave) {
+                    let e = spawn_asteroid(&mut commands, &mm, &spec);
+                    commands.entity(e).insert(WaveMember);
+                }
+                waves.phase = WavePhase::Active;
+                waves.timer = Timer::from_seconds(WAVE_MAX_DURATION, TimerMode::Once);
+            }
+        },
+        WavePhase::Active => {
+            if waves.timer.finished() || members_q.is_empty() {
+                for e in members_q.iter() {
+                    commands.entity(e).remove::<WaveMember>();
+                }
+                waves.phase = WavePhase::Calm;
+                waves.timer = Timer::from_seconds(WAVE_CALM, TimerMode::Once);
+            }
+        }
+    }
+}
+
+// ---
+
+fn update_indicators(
+    waves: Res<Waves>,
+    mut writer: EventWriter<UpdateWidgets>
+) {
+    let next = if waves.phase == WavePhase::Calm {waves.timer.remaining_secs().ceil()} else {0.};
+    writer.send(UpdateWidgets(vec![
+        WidgetUpdateData::from_key_value(I_WAVE.0, waves.wave as f32),
+        WidgetUpdateData::from_key_value(I_NEXT_WAVE.0, next),
+    ]));
+}
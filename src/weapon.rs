@@ -0,0 +1,379 @@
+use bevy::prelude::*;
+use crate::{GameState, NotReady};
+use crate::drone::{Drone, Fluel, Manual};
+use crate::missile::Missiles;
+use crate::laser::LaserHeat;
+use crate::ui::{RegisterWidgets, ULayout, UpdateWidgets, WType, WidgetRegData, WidgetUpdateData};
+
+pub struct WeaponPlugin;
+impl Plugin for WeaponPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn);
+        app.add_systems(Update, setup.run_if(in_state(GameState::Setup)));
+        app.add_systems(Update, (input, update_indicators).run_if(in_state(GameState::Game)));
+        app.add_event::<WeaponShot>();
+    }
+}
+
+// ---
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Projectile {
+    Missile,
+    Cluster,
+    Beam,
+    Slug
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WeaponCost {
+    Ammo(i32),
+    Energy(f32)
+}
+
+// Beams are continuous: damage and energy cost are per second of fire
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Weapon {
+    pub name: &'static str,
+    pub projectile: Projectile,
+    pub fire_rate: f32,      // shots (ticks for beams) per second
+    pub cost: WeaponCost,
+    pub damage: f32,
+    pub range: f32
+}
+
+impl Weapon {
+    pub fn is_continuous(&self) -> bool {
+        self.projectile == Projectile::Beam
+    }
+
+    pub fn cost_for(&self, duration: f32) -> WeaponCost {
+        match self.cost {
+            WeaponCost::Energy(v) if self.is_continuous() => WeaponCost::Energy(v * duration),
+            c => c
+        }
+    }
+}
+
+pub const MISSILE: Weapon = Weapon {
+    name: "Missile",
+    projectile: Projectile::Missile,
+    fire_rate: 2.,
+    cost: WeaponCost::Ammo(1),
+    damage: 10.,
+    range: 600.
+};
+
+pub const CLUSTER_MISSILE: Weapon = Weapon {
+    name: "Cluster",
+    projectile: Projectile::Cluster,
+    fire_rate: 0.5,
+    cost: WeaponCost::Ammo(3),
+    damage: 4.,
+    range: 400.
+};
+
+pub const LASER: Weapon = Weapon {
+    name: "Laser",
+    projectile: Projectile::Beam,
+    fire_rate: 60.,
+    cost: WeaponCost::Energy(12.),
+    damage: 3.,
+    range: 200.
+};
+
+pub const MINING_LASER: Weapon = Weapon {
+    name: "Mining",
+    projectile: Projectile::Beam,
+    fire_rate: 60.,
+    cost: WeaponCost::Energy(6.),
+    damage: 1.2,
+    range: 60.
+};
+
+pub const KINETIC_CANNON: Weapon = Weapon {
+    name: "Cannon",
+    projectile: Projectile::Slug,
+    fire_rate: 8.,
+    cost: WeaponCost::Energy(1.),
+    damage: 1.,
+    range: 400.
+};
+
+#[derive(Component)]
+pub struct Armory {
+    weapons: Vec<Weapon>,
+    selected: usize,
+    next_shot: f32,
+    held: f32
+}
+
+impl Armory {
+    pub fn new(weapons: Vec<Weapon>) -> Self {
+        Self {weapons, selected: 0, next_shot: 0., held: 0.}
+    }
+
+    pub fn current(&self) -> &Weapon {
+        &self.weapons[self.selected]
+    }
+
+    pub fn cycle(&mut self) {
+        self.selected = (self.selected + 1) % self.weapons.len();
+        self.held = 0.;
+    }
+
+    // Some(seconds of fire covered by the shot) when the weapon may fire.
+    // A beam accumulates the time the trigger is held and releases it at most fire_rate times per second,
+    // so the same hold gives the same damage and cost at any frame rate
+
+    pub fn trigger(&mut self, now: f32, dt: f32) -> Option<f32> {
+        let interval = 1. / self.current().fire_rate;
+        if self.current().is_continuous() {
+            self.held += dt;
+            if self.held < interval {
+                return None;
+            }
+            let duration = self.held;
+            self.held = 0.;
+            return Some(duration);
+        }
+        if now < self.next_shot {
+            return None;
+        }
+        self.next_shot = now + interval;
+        Some(interval)
+    }
+
+    // Letting go of a beam fires what is left of the hold, so short presses aren't lost at low frame rates
+
+    pub fn release(&mut self) -> Option<f32> {
+        let duration = self.held;
+        self.held = 0.;
+        (duration > 0.).then_some(duration)
+    }
+}
+
+// Shooter, weapon, seconds of fire (matters for beams only)
+
+#[derive(Event)]
+pub struct WeaponShot(pub Entity, pub Weapon, pub f32);
+
+// Target picked by a shooter on its own (turrets), the player's shots go to the locked Target
+
+#[derive(Component)]
+pub struct Aim(pub Entity);
+
+#[derive(Component)]
+pub struct WeaponTempMarker;
+
+const I_WEAPON: (&str, &str) = ("weapon", "Weapon");
+
+// ---
+
+fn spawn(
+    mut commands: Commands,
+) {
+    commands.spawn((NotReady, WeaponTempMarker));
+}
+
+// ---
+
+fn setup (
+    mut commands: Commands,
+    drones_q: Query<Entity, (With<Drone>, Without<Armory>)>,
+    check_q: Query<Entity, (With<NotReady>, With<WeaponTempMarker>)>,
+    mut writer: EventWriter<RegisterWidgets>,
+) {
+    if drones_q.is_empty() {
+        if let Ok(e) = check_q.get_single() {
+            commands.entity(e).despawn();
+        }
+        return;
+    }
+    for de in drones_q.iter() {
+        commands.entity(de).insert(Armory::new(vec![MISSILE, CLUSTER_MISSILE, LASER, MINING_LASER, KINETIC_CANNON]));
+    }
+    writer.send(
+        RegisterWidgets(
+            vec![
+                WidgetRegData {
+                    key: I_WEAPON.0,
+                    label: I_WEAPON.1,
+                    parent: ULayout::Footer,
+                    wtype: WType::Text,
+                    image: None,
+                    start: 1,
+                    span: 3,
+                    default: None
+                },
+            ]
+        )
+    );
+}
+
+// ---
+
+fn input(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut drone_q: Query<(Entity, &mut Armory, Option<&mut Missiles>, Option<&mut Fluel>, Option<&LaserHeat>), With<Manual>>,
+    mut ev_writer: EventWriter<WeaponShot>,
+    time: Res<Time>
+) {
+    let Ok((de, mut armory, om, of, heat)) = drone_q.get_single_mut() else {
+        return;
+    };
+
+    if keys.just_pressed(KeyCode::KeyQ) {
+        armory.cycle();
+    }
+
+    // beams fire while the trigger is held, everything else once per press
+
+    let weapon = *armory.current();
+    let firing = if weapon.is_continuous() {keys.pressed(KeyCode::ControlLeft)} else {keys.just_pressed(KeyCode::ControlLeft)};
+    // only touched when something is held, so the widget sees Changed<Armory> on real changes only
+    let shot = if firing {
+        armory.trigger(time.elapsed_seconds(), time.delta_seconds())
+    } else if armory.held > 0. {
+        armory.release()
+    } else {
+        return;
+    };
+
+    if let Some(duration) = shot {
+        // overheated laser emitters stay silent, they cost nothing
+        let cost = match (weapon.cost_for(duration), heat) {
+            (WeaponCost::Energy(v), Some(h)) if weapon.is_continuous() => {
+                if h.active_share() <= 0. {
+                    return;
+                }
+                WeaponCost::Energy(v * h.active_share())
+            },
+            (c, _) => c
+        };
+        if pay(cost, om, of) {
+            ev_writer.send(WeaponShot(de, weapon, duration));
+        }
+    }
+}
+
+// ---
+
+// Where a shot starts. A top-level shooter's Transform is up to date in Update, its GlobalTransform is a frame old.
+// Mounted shooters (turrets) sit on static parents, so the parent's GlobalTransform with this frame's local turn is current
+
+pub fn shooter_transform(transform: &Transform, parent: Option<&Parent>, globals_q: &Query<&GlobalTransform>) -> Transform {
+    match parent.and_then(|p| globals_q.get(p.get()).ok()) {
+        Some(pg) => pg.mul_transform(*transform).compute_transform(),
+        None => *transform
+    }
+}
+
+// ---
+
+pub fn pay(cost: WeaponCost, missiles: Option<Mut<Missiles>>, fluel: Option<Mut<Fluel>>) -> bool {
+    match cost {
+        WeaponCost::Ammo(n) => {
+            let Some(mut m) = missiles else {
+                return false;
+            };
+            m.take(n)
+        },
+        WeaponCost::Energy(v) => {
+            let Some(mut f) = fluel else {
+                return false;
+            };
+            if f.get() < v {
+                return false;
+            }
+            f.loss(v);
+            true
+        }
+    }
+}
+
+// ---
+
+fn update_indicators(
+    drone_q: Query<&Armory, (With<Manual>, Changed<Armory>)>,
+    mut writer: EventWriter<UpdateWidgets>
+) {
+    if let Ok(armory) = drone_q.get_single() {
+        writer.send(UpdateWidgets(vec![
+            WidgetUpdateData::from_key_text(I_WEAPON.0, armory.current().name)
+        ]));
+    }
+}
+
+// ---
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Holds the trigger for `seconds` at a fixed frame time, returns the times of the shots,
+    // the seconds of fire they cover and the energy they cost
+
+    fn hold(weapon: Weapon, seconds: f32, dt: f32) -> (Vec<f32>, f32, f32) {
+        let mut armory = Armory::new(vec![weapon]);
+        let frames = (seconds / dt).round() as usize;
+        let mut shots = Vec::new();
+        let mut fired = 0.;
+        let mut cost = 0.;
+        let mut count = |now: f32, duration: f32| {
+            shots.push(now);
+            fired += duration;
+            if let WeaponCost::Energy(v) = weapon.cost_for(duration) {
+                cost += v;
+            }
+        };
+        for i in 0 .. frames {
+            if let Some(duration) = armory.trigger(i as f32 * dt, dt) {
+                count(i as f32 * dt, duration);
+            }
+        }
+        // trigger let go after the last frame
+        if let Some(duration) = armory.release() {
+            count(frames as f32 * dt, duration);
+        }
+        (shots, fired, cost)
+    }
+
+    #[test]
+    fn beam_hold_is_frame_rate_independent() {
+        let (_, fired_30, cost_30) = hold(LASER, 2., 1. / 30.);
+        let (_, fired_144, cost_144) = hold(LASER, 2., 1. / 144.);
+        assert!((fired_30 - fired_144).abs() < 1e-3, "{fired_30} vs {fired_144}");
+        assert!((cost_30 - cost_144).abs() < 1e-2, "{cost_30} vs {cost_144}");
+        assert!((fired_30 - 2.).abs() < 1e-3);
+    }
+
+    // 0.1 s is 3 frames at 30 fps and 14 at 144 fps, not a whole number of beam ticks:
+    // the end of the hold must be fired on release
+
+    #[test]
+    fn beam_release_fires_the_rest_of_the_hold() {
+        for dt in [1. / 30., 1. / 144.] {
+            let held = (0.1 / dt).round() * dt;
+            let (_, fired, cost) = hold(LASER, 0.1, dt);
+            assert!((fired - held).abs() < 1e-4, "dt {dt}: fired {fired} of {held}");
+            if let WeaponCost::Energy(rate) = LASER.cost {
+                assert!((cost - rate * held).abs() < 1e-3, "dt {dt}: cost {cost}");
+            }
+        }
+    }
+
+    #[test]
+    fn discrete_weapon_respects_fire_rate() {
+        for weapon in [MISSILE, CLUSTER_MISSILE, KINETIC_CANNON] {
+            for dt in [1. / 30., 1. / 144.] {
+                let (shots, _, _) = hold(weapon, 3., dt);
+                assert!(!shots.is_empty());
+                for pair in shots.windows(2) {
+                    assert!(pair[1] - pair[0] >= 1. / weapon.fire_rate - 1e-4, "{} at dt {dt}: {:?}", weapon.name, pair);
+                }
+            }
+        }
+    }
+}